@@ -110,12 +110,25 @@ _vex() {{
                 "rename[Rename a saved QEMU configuration]" \
                 "rm[Remove a saved QEMU configuration]" \
                 "list[List all saved QEMU configurations]" \
+                "edit[Edit a saved QEMU configuration in $VISUAL or $EDITOR]" \
+                "convert[Convert a saved configuration to another file format]" \
+                "migrate[Upgrade saved configurations to the current schema version]" \
                 "exec[Execute a saved QEMU configuration]" \
+                "history[Show past exec runs]" \
+                "ps[List running QEMU instances]" \
+                "stop[Stop a running QEMU instance gracefully]" \
+                "kill[Kill a running QEMU instance immediately]" \
+                "logs[Show QEMU output logs of current and past runs]" \
+                "ssh[Open an SSH session to a running QEMU instance]" \
+                "qmp[Send a QMP command to a running QEMU instance]" \
+                "snapshot[Manage snapshots of a configuration's qcow2 drives]" \
+                "up[Start all VMs of a compose file]" \
+                "down[Stop all VMs of a compose file]" \
                 "completions[Generate shell completion scripts]"
             ;;
         args)
             case $line[1] in
-                exec|rm)
+                exec|rm|history|migrate|convert|edit)
                     _vex_configs
                     ;;
                 rename)
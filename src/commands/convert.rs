@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+
+use crate::config::{ConfigFormat, config_file, config_file_as, read_config, write_config};
+
+#[derive(Args)]
+#[clap(about = "Convert a saved configuration to another file format")]
+pub struct ConvertArgs {
+    #[arg(help = "Configuration name to convert")]
+    pub name: String,
+
+    #[arg(
+        long = "to",
+        value_name = "json|toml|yaml",
+        help = "Target file format"
+    )]
+    pub to: ConfigFormat,
+}
+
+pub fn convert_command(name: String, to: ConfigFormat) -> Result<()> {
+    let config_path = config_file(&name)?;
+    if !config_path.exists() {
+        anyhow::bail!(
+            "Configuration '{}' does not exist. Use 'vex list' to see available configurations",
+            name
+        );
+    }
+
+    let from = ConfigFormat::from_path(&config_path).unwrap_or(ConfigFormat::Json);
+    if from == to {
+        println!("Configuration '{}' is already stored as {}", name, to);
+        return Ok(());
+    }
+
+    // Convert the stored form, not the resolved one, so `extends` stays intact
+    let config = read_config(&name, &config_path)?;
+    let new_path = config_file_as(&name, to)?;
+    write_config(&new_path, &config)?;
+    fs::remove_file(&config_path).context("Failed to delete old config file")?;
+
+    println!(
+        "Configuration '{}' converted from {} to {}: {:?}",
+        name, from, to, new_path
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use clap::Args;
+use std::path::PathBuf;
+
+use crate::commands::stop::{finish_exited_instances, stop_command};
+use crate::compose::{DEFAULT_COMPOSE_FILE, instance_name, load_compose};
+use crate::instance::find_running_instance;
+
+#[derive(Args)]
+#[clap(about = "Stop all VMs of a compose file in reverse dependency order")]
+pub struct DownArgs {
+    #[arg(
+        short = 'f',
+        long = "file",
+        default_value = DEFAULT_COMPOSE_FILE,
+        help = "Compose file to use"
+    )]
+    pub file: PathBuf,
+
+    #[arg(
+        short = 't',
+        long = "timeout",
+        default_value_t = 10,
+        help = "Seconds to wait for each VM before killing it"
+    )]
+    pub timeout: u64,
+}
+
+pub fn down_command(file: PathBuf, timeout: u64) -> Result<()> {
+    let compose = load_compose(&file)?;
+    let project = compose.project_name(&file);
+
+    let mut stopped = 0;
+    for vm in compose.start_order()?.into_iter().rev() {
+        let name = instance_name(&project, vm);
+        if find_running_instance(&name)?.is_some() {
+            stop_command(name, timeout)?;
+            stopped += 1;
+        } else if finish_exited_instances(&name)? {
+            stopped += 1;
+        }
+    }
+
+    if stopped == 0 {
+        println!("No VMs of project '{}' are running.", project);
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use crate::commands::exec::{ExecArgs, exec_command};
+use crate::config::{
+    ConfigFormat, QemuConfig, config_file, parse_config, resolve_extends, validate_config,
+};
+use crate::error::VexError;
+use crate::utils::io::{prompt_user_default_no, prompt_user_eof_no, prompt_user_explicit};
+use crate::utils::shell::shell_split;
+
+/// Prefix of the error lines put at the top of TOML and YAML files when reopening them
+const EDIT_ERROR_PREFIX: &str = "# vex: ";
+
+#[derive(Args)]
+#[clap(about = "Edit a saved QEMU configuration in $VISUAL or $EDITOR")]
+pub struct EditArgs {
+    #[arg(help = "Configuration name to edit")]
+    pub name: String,
+}
+
+pub fn edit_command(name: String) -> Result<()> {
+    let config_path = config_file(&name)?;
+    if !config_path.exists() {
+        anyhow::bail!(
+            "Configuration '{}' does not exist. Create it first with 'vex save'",
+            name
+        );
+    }
+    let format = ConfigFormat::from_path(&config_path).unwrap_or(ConfigFormat::Json);
+    let original = fs::read_to_string(&config_path).context("Failed to read config file")?;
+
+    // Edit a copy, so the saved configuration stays intact until the edit is accepted.
+    // The copy is created exclusively and readable only by the user; it is removed on drop.
+    let mut edit_file = tempfile::Builder::new()
+        .prefix("vex-edit-")
+        .suffix(&format!(".{}", format.extension()))
+        .tempfile()
+        .context("Failed to create temporary file")?;
+    edit_file
+        .write_all(original.as_bytes())
+        .context("Failed to write temporary file")?;
+    let result = edit_loop(&name, format, &original, edit_file.path());
+
+    match result? {
+        Some(content) => {
+            fs::write(&config_path, content).context("Failed to save config file")?;
+            println!("Configuration '{}' updated", name);
+        }
+        None => println!("Configuration '{}' left unchanged", name),
+    }
+    Ok(())
+}
+
+/// Run the editor until the file parses and validates, returning the content to save
+fn edit_loop(
+    name: &str,
+    format: ConfigFormat,
+    original: &str,
+    edit_path: &Path,
+) -> Result<Option<String>> {
+    loop {
+        run_editor(edit_path)?;
+        let edited = fs::read_to_string(edit_path).context("Failed to read edited file")?;
+        let content = strip_error_header(&edited);
+        if content == original {
+            println!("No changes made");
+            return Ok(None);
+        }
+
+        let config = match check_edit(name, &content, format) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("Error: {:#}", err);
+                println!("Reopen the editor to fix it? [Y/n]");
+                if !prompt_user_eof_no()? {
+                    println!("Edit discarded");
+                    return Ok(None);
+                }
+                fs::write(edit_path, with_error_header(&content, format, &err))
+                    .context("Failed to update temporary file")?;
+                continue;
+            }
+        };
+
+        println!("Trial run the edited configuration before saving it? [y/N]");
+        if !prompt_user_default_no()? {
+            return Ok(Some(content));
+        }
+        let keep = match trial_run(name, config) {
+            Ok(()) => {
+                println!("Keep the edited configuration? [Y/n]");
+                prompt_user_eof_no()?
+            }
+            Err(err) => match err.downcast_ref::<VexError>() {
+                // QEMU ran and ended with a status, e.g. 130 after Ctrl-C or a guest poweroff
+                // reporting an error; that says nothing about the edit, so no answer is assumed
+                Some(VexError::Exit { code, .. }) => {
+                    println!("Trial run finished with exit code {}", code);
+                    println!("Keep the edited configuration? [y/n]");
+                    prompt_user_explicit()?
+                }
+                _ => {
+                    eprintln!("Trial run failed: {:#}", err);
+                    println!("Keep the edited configuration anyway? [y/N]");
+                    prompt_user_default_no()?
+                }
+            },
+        };
+        return Ok(keep.then_some(content));
+    }
+}
+
+/// Open `path` in `$VISUAL`, `$EDITOR` or `vi`, which may include arguments such as `code --wait`
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let argv = shell_split(&editor)
+        .map_err(|err| anyhow::anyhow!("Invalid editor command '{}': {}", editor, err))?;
+    let (program, args) = argv
+        .split_first()
+        .with_context(|| format!("Invalid editor command '{}'", editor))?;
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", editor))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}; edit discarded", editor, status);
+    }
+    Ok(())
+}
+
+/// Parse the edited file and validate it with its `extends` chain applied
+fn check_edit(name: &str, content: &str, format: ConfigFormat) -> Result<QemuConfig> {
+    let (config, _) = parse_config(name, content, format)?;
+    let inherited = resolve_extends(name, config.clone())?;
+    validate_config(&inherited.config)?;
+    Ok(config)
+}
+
+/// Run the edited configuration in the foreground without saving it first
+fn trial_run(name: &str, config: QemuConfig) -> Result<()> {
+    exec_command(ExecArgs {
+        name: Some(name.to_string()),
+        debug: false,
+        gdb_port: None,
+        full: false,
+        detach: false,
+        from_snapshot: None,
+        dry_run: false,
+        json: false,
+        extra_args: Vec::new(),
+        sets: Vec::new(),
+        timeout: None,
+        expect: Vec::new(),
+        fail_on: Vec::new(),
+        rerun: None,
+        env_files: Vec::new(),
+        cwd: None,
+        config: Some(Box::new(config)),
+    })
+}
+
+/// Put the error at the top of formats with comments, so it shows in the editor
+fn with_error_header(content: &str, format: ConfigFormat, err: &anyhow::Error) -> String {
+    if format == ConfigFormat::Json {
+        // JSON has no comments; the error was printed before reopening
+        return content.to_string();
+    }
+    let mut header: String = format!("{:#}", err)
+        .lines()
+        .map(|line| format!("{}{}\n", EDIT_ERROR_PREFIX, line))
+        .collect();
+    header.push_str(content);
+    header
+}
+
+/// Remove the error lines added by [`with_error_header`]
+fn strip_error_header(content: &str) -> String {
+    let mut rest = content;
+    while rest.starts_with(EDIT_ERROR_PREFIX) {
+        rest = rest.split_once('\n').map_or("", |(_, tail)| tail);
+    }
+    rest.to_string()
+}
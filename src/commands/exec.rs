@@ -1,36 +1,434 @@
 use anyhow::{Context, Result};
 use clap::Args;
 use regex::Regex;
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::process::Command;
+use std::io::{self, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::config::{QemuConfig, config_file};
-use crate::utils::qemu::get_qemu_version;
+use crate::commands::ssh::SSH_GUEST_PORT;
+use crate::config::params::parse_set;
+use crate::config::{
+    GdbPort, PortForward, Protocol, QemuConfig, config_file, load_config, load_env_files,
+    merge_variables, resolve_extends, resolve_params, validate_config,
+};
+use crate::error::VexError;
+use crate::instance::types::unix_now;
+use crate::instance::{
+    Instance, RunRecord, append_history, create_run_log, find_run, instances_dir, prune_run_logs,
+    remove_instance, save_instance,
+};
+use crate::utils::expect::{ExpectMatcher, ExpectOutcome};
+use crate::utils::hooks::{run_post_hooks, run_pre_hooks};
+use crate::utils::net::{
+    find_free_port, find_free_proto_port, is_addr_port_free, is_port_free, is_proto_port_free,
+};
+use crate::utils::process::{
+    exit_code, forward_signals_to, process_start_time, send_signal, stop_forwarding_signals,
+    wait_timeout,
+};
+use crate::utils::qemu::{
+    add_host_forwards, find_host_forward, get_qemu_version, host_forward_ports, merge_args,
+};
+use crate::utils::qemu_options::{tokenize, untokenize};
+use crate::utils::shell::shell_join;
+use crate::utils::sidecar::{SidecarRun, start_sidecars, stop_sidecars};
+use crate::utils::time::{format_duration, parse_duration};
+
+/// `${VAR}`, `${VAR:-default}`, `${VAR:?message}` and the `$${...}` escape
+const VARIABLE_PATTERN: &str = r"\$(\$)?\{([^}:]+)(?::([-?])([^}]*))?\}";
+
+/// Port used by QEMU's `-s` shorthand, preferred when no port is configured
+const DEFAULT_GDB_PORT: u16 = 1234;
+
+/// Options that start a GDB server or pause the CPU, replaced by `-d`
+const GDB_OPTIONS: &[&str] = &["-gdb", "-s", "-S"];
+
+/// Exit code when `--timeout` expires, matching coreutils `timeout`
+pub const TIMEOUT_EXIT_CODE: u8 = 124;
+
+/// Exit code when a `--fail-on` pattern matches or `--expect` never matched.
+///
+/// Even, so `isa-debug-exit` (which exits with `(value << 1) | 1`) can't produce it.
+pub const EXPECT_FAILED_EXIT_CODE: u8 = 122;
 
 #[derive(Args)]
 #[clap(about = "Execute a saved QEMU configuration")]
 pub struct ExecArgs {
-    #[arg(help = "Configuration name to execute")]
-    pub name: String,
+    #[arg(
+        required_unless_present = "rerun",
+        help = "Configuration name to execute"
+    )]
+    pub name: Option<String>,
 
     #[arg(
         short = 'd',
         long = "debug",
-        help = "Start QEMU in debug mode (GDB server on port 1234)"
+        help = "Start QEMU in debug mode (GDB server, port 1234 by default)"
     )]
     pub debug: bool,
 
+    #[arg(
+        long = "gdb-port",
+        value_name = "N|auto",
+        requires = "debug",
+        help = "GDB server port for debug mode, or 'auto' to pick a free one"
+    )]
+    pub gdb_port: Option<GdbPort>,
+
     #[arg(
         short = 'f',
         long = "full",
         help = "Show full QEMU command line arguments"
     )]
     pub full: bool,
+
+    #[arg(
+        long = "detach",
+        help = "Run QEMU in the background and track it as an instance (see 'vex ps')"
+    )]
+    pub detach: bool,
+
+    #[arg(
+        long = "from-snapshot",
+        value_name = "TAG",
+        help = "Start from a snapshot created with 'vex snapshot create' (adds -loadvm)"
+    )]
+    pub from_snapshot: Option<String>,
+
+    #[arg(
+        long = "dry-run",
+        conflicts_with = "detach",
+        help = "Print the final QEMU command instead of running it"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long = "json",
+        requires = "dry_run",
+        help = "With --dry-run, print the command as a JSON argv array"
+    )]
+    pub json: bool,
+
+    #[arg(
+        last = true,
+        help = "Extra QEMU arguments after '--'; single-valued options like -m replace saved values"
+    )]
+    pub extra_args: Vec<String>,
+
+    #[arg(
+        long = "set",
+        value_name = "NAME=VALUE",
+        value_parser = parse_set,
+        help = "Set a parameter declared by the configuration (repeatable)"
+    )]
+    pub sets: Vec<(String, String)>,
+
+    #[arg(
+        long = "timeout",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        conflicts_with = "detach",
+        help = "Terminate QEMU after this long (e.g. 90s, 5m) and exit with code 124"
+    )]
+    pub timeout: Option<Duration>,
+
+    #[arg(
+        long = "expect",
+        value_name = "PATTERN",
+        conflicts_with = "detach",
+        help = "Exit successfully once QEMU's output matches this regex (repeatable)"
+    )]
+    pub expect: Vec<String>,
+
+    #[arg(
+        long = "fail-on",
+        value_name = "PATTERN",
+        conflicts_with = "detach",
+        help = "Exit with code 122 once QEMU's output matches this regex (repeatable)"
+    )]
+    pub fail_on: Vec<String>,
+
+    #[arg(
+        long = "rerun",
+        value_name = "ID",
+        conflicts_with_all = ["name", "debug", "gdb_port", "from_snapshot", "extra_args", "sets", "env_files"],
+        help = "Replay the exact QEMU command of a past run (see 'vex history')"
+    )]
+    pub rerun: Option<u64>,
+
+    #[arg(
+        long = "env-file",
+        value_name = "FILE",
+        help = "Load variables for substitution from a KEY=VALUE file (repeatable)"
+    )]
+    pub env_files: Vec<PathBuf>,
+
+    #[arg(
+        long = "cwd",
+        value_name = "DIR",
+        help = "Run QEMU from this directory instead of the one saved with the configuration"
+    )]
+    pub cwd: Option<PathBuf>,
+
+    /// Configuration to run instead of loading `name`, e.g. an inline compose entry
+    #[arg(skip)]
+    pub config: Option<Box<QemuConfig>>,
 }
 
-/// TODO: Currently the debug port is fixed at 1234. It should be adaptive or configurable.
-pub fn exec_command(name: String, debug: bool, full: bool) -> Result<()> {
-    let config_path = config_file(&name)?;
+pub fn exec_command(args: ExecArgs) -> Result<()> {
+    let ExecArgs {
+        name,
+        debug,
+        gdb_port,
+        full,
+        detach,
+        from_snapshot,
+        dry_run,
+        json,
+        extra_args,
+        sets,
+        timeout,
+        expect,
+        fail_on,
+        rerun,
+        env_files,
+        cwd,
+        config: inline_config,
+    } = args;
+
+    let matcher = if expect.is_empty() && fail_on.is_empty() {
+        None
+    } else {
+        Some(ExpectMatcher::new(&expect, &fail_on)?)
+    };
+
+    let mut plan = match (rerun, name) {
+        (Some(id), _) => {
+            let record = find_run(id)?;
+            let plan = RunPlan {
+                desc: Some(format!("replay of run #{}", record.id)),
+                qemu_version: None,
+                name: record.config,
+                qemu_bin: record.qemu_bin,
+                args: record.args,
+                debug: record.debug,
+                gdb_port: record.gdb_port,
+                // Recorded args already carry the forwards
+                forwards: Vec::new(),
+                params: record.params,
+                pre_exec: record.pre_exec,
+                post_exec: record.post_exec,
+                sidecars: record.sidecars,
+                cwd: cwd.or(record.cwd),
+            };
+            check_cwd(&plan.name, plan.cwd.as_deref())?;
+            check_replayed_ports(&plan, record.id)?;
+            plan
+        }
+        (None, Some(name)) => {
+            let mut config = match inline_config {
+                // Inline configurations (e.g. compose VMs) may extend saved ones
+                Some(config) => resolve_extends(&name, *config)?.config,
+                None => load_exec_config(&name)?,
+            };
+            if cwd.is_some() {
+                config.cwd = cwd;
+            }
+            check_cwd(&name, config.cwd.as_deref())?;
+            plan_from_config(
+                name,
+                config,
+                debug,
+                gdb_port,
+                &extra_args,
+                &sets,
+                &env_files,
+            )?
+        }
+        (None, None) => anyhow::bail!("A configuration name or '--rerun <ID>' is required"),
+    };
+
+    if let Some(tag) = from_snapshot {
+        plan.args.push("-loadvm".to_string());
+        plan.args.push(tag);
+    }
+
+    // Version warnings would only clutter a dry-run's output
+    if !dry_run && let Some(saved_ver) = &plan.qemu_version {
+        warn_on_version_mismatch(&plan.qemu_bin, saved_ver);
+    }
+
+    // Give every run its own QMP socket so `vex qmp` can reach it; a dry-run shows it too,
+    // so the printed command is the one that would run
+    let qmp_socket = instances_dir()?.join(format!("{}-{}.qmp", plan.name, std::process::id()));
+    let mut exec_args = plan.args.clone();
+    exec_args.push("-qmp".to_string());
+    exec_args.push(format!("unix:{},server=on,wait=off", qmp_socket.display()));
+
+    if dry_run {
+        let argv: Vec<&str> = std::iter::once(plan.qemu_bin.as_str())
+            .chain(exec_args.iter().map(String::as_str))
+            .collect();
+        if json {
+            println!("{}", serde_json::to_string(&argv)?);
+        } else {
+            println!("{}", shell_join(&argv));
+        }
+        return Ok(());
+    }
+
+    let hook_env = plan.hook_env();
+    let cwd = plan.cwd.as_deref();
+    if let Err(err) = run_pre_hooks(&plan.pre_exec, &hook_env, cwd) {
+        // Post-exec hooks clean up after pre-exec hooks that did run
+        let _ = run_post_hooks(&plan.post_exec, &hook_env, cwd);
+        return Err(err);
+    }
+
+    let (log_path, log) = create_run_log(&plan.name)?;
+    prune_run_logs(&plan.name, &log_path)?;
+
+    let sidecar_pids = match start_sidecars(&plan.sidecars, &log, &log_path, cwd) {
+        Ok(pids) => pids,
+        Err(err) => {
+            let _ = run_post_hooks(&plan.post_exec, &hook_env, cwd);
+            return Err(err);
+        }
+    };
+
+    let _ = fs::remove_file(&qmp_socket);
+    let mut sockets = BTreeMap::from([("qmp".to_string(), qmp_socket.clone())]);
+    for sidecar in &plan.sidecars {
+        sockets.insert(format!("sidecar-{}", sidecar.name), sidecar.socket.clone());
+    }
+    // Print startup message
+    print_startup_message(&plan, &exec_args, full);
+
+    let launch = Launch {
+        name: &plan.name,
+        qemu_bin: &plan.qemu_bin,
+        args: exec_args,
+        gdb_port: plan.gdb_port,
+        sockets,
+        post_exec: &plan.post_exec,
+        hook_env: &hook_env,
+        sidecars: &sidecar_pids,
+        log_path,
+        cwd,
+        pid: Cell::new(None),
+    };
+
+    let started_at = unix_now();
+    let timer = Instant::now();
+    let result = if detach {
+        spawn_detached(&launch, log)
+    } else {
+        run_foreground(&launch, log, timeout, matcher)
+    };
+    // A detached instance stops its sidecars when it is stopped
+    if !(detach && result.is_ok()) {
+        stop_sidecars(&sidecar_pids);
+    }
+
+    let exit_code = match &result {
+        _ if detach => None,
+        Ok(()) => Some(0),
+        Err(err) => match err.downcast_ref::<VexError>() {
+            Some(VexError::Exit { code, .. }) => Some(*code),
+            _ => None,
+        },
+    };
+    append_history(RunRecord {
+        id: 0,
+        config: plan.name.clone(),
+        qemu_bin: plan.qemu_bin.clone(),
+        args: plan.args.clone(),
+        started_at,
+        duration_secs: (!detach).then(|| timer.elapsed().as_secs()),
+        exit_code,
+        debug: plan.debug,
+        detached: detach,
+        gdb_port: plan.gdb_port,
+        params: plan.params.clone(),
+        pre_exec: plan.pre_exec.clone(),
+        post_exec: plan.post_exec.clone(),
+        sidecars: plan.sidecars.clone(),
+        cwd: plan.cwd.clone(),
+        instance: launch
+            .pid
+            .get()
+            .map(|pid| Instance::make_id(&plan.name, pid)),
+        log_file: Some(launch.log_path.clone()),
+    })?;
+
+    // A detached instance runs its post-exec hooks when it is stopped
+    if detach && result.is_ok() {
+        return result;
+    }
+    let mut post_env = hook_env;
+    if let Some(code) = exit_code {
+        post_env.insert("VEX_EXIT_CODE".to_string(), code.to_string());
+    }
+    let post_result = run_post_hooks(&plan.post_exec, &post_env, cwd);
+    result.and(post_result)
+}
+
+/// Warn when the QEMU binary differs in version from the one a configuration was saved with
+fn warn_on_version_mismatch(qemu_bin: &str, saved_ver: &str) {
+    match get_qemu_version(qemu_bin) {
+        Some(curr) if curr != saved_ver => {
+            println!("WARNING: Version mismatch!");
+            println!("   Configuration saved with QEMU {}", saved_ver);
+            println!("   Current system has QEMU {}", curr);
+            println!("   Some features might not work as expected.\n");
+        }
+        None => {
+            println!("WARNING: Could not detect current QEMU version.\n");
+        }
+        _ => {} // Versions match, all good
+    }
+}
+
+/// Resolved QEMU command for one `vex exec` invocation
+struct RunPlan {
+    name: String,
+    desc: Option<String>,
+    /// QEMU version the configuration was saved with
+    qemu_version: Option<String>,
+    qemu_bin: String,
+    args: Vec<String>,
+    debug: bool,
+    gdb_port: Option<u16>,
+    /// Port forwards with their host ports resolved
+    forwards: Vec<PortForward>,
+    params: BTreeMap<String, String>,
+    pre_exec: Vec<String>,
+    post_exec: Vec<String>,
+    sidecars: Vec<SidecarRun>,
+    /// Directory QEMU, hooks and sidecars run from
+    cwd: Option<PathBuf>,
+}
+
+impl RunPlan {
+    /// Environment for hooks: the resolved parameters plus `VEX_CONFIG`
+    fn hook_env(&self) -> BTreeMap<String, String> {
+        let mut env = self.params.clone();
+        env.insert("VEX_CONFIG".to_string(), self.name.clone());
+        env
+    }
+}
+
+/// Load the saved configuration to execute
+fn load_exec_config(name: &str) -> Result<QemuConfig> {
+    let config_path = config_file(name)?;
     if !config_path.exists() {
         anyhow::bail!(
             "Configuration '{}' does not exist. Create it first with 'vex save'",
@@ -38,94 +436,618 @@ pub fn exec_command(name: String, debug: bool, full: bool) -> Result<()> {
         );
     }
 
-    let config_json = fs::read_to_string(&config_path).context("Failed to read config file")?;
-    let config: QemuConfig =
-        serde_json::from_str(&config_json).context("Failed to deserialize configuration")?;
+    let config = load_config(name)?;
+    validate_config(&config)?;
+    Ok(config)
+}
 
-    if let Some(saved_ver) = &config.qemu_version {
-        let current_ver = get_qemu_version(&config.qemu_bin);
-        match current_ver {
-            Some(curr) if curr != *saved_ver => {
-                println!("WARNING: Version mismatch!");
-                println!("   Configuration saved with QEMU {}", saved_ver);
-                println!("   Current system has QEMU {}", curr);
-                println!("   Some features might not work as expected.\n");
+/// Build the QEMU command from a configuration, parameters and overrides
+fn plan_from_config(
+    name: String,
+    config: QemuConfig,
+    debug: bool,
+    gdb_port: Option<GdbPort>,
+    extra_args: &[String],
+    sets: &[(String, String)],
+    env_files: &[PathBuf],
+) -> Result<RunPlan> {
+    let params = resolve_params(&config, sets)
+        .with_context(|| format!("Invalid parameters for configuration '{}'", name))?;
+    let file_vars = load_env_files(&name, env_files)?;
+    let params = merge_variables(&params, &file_vars);
+
+    // Sidecar sockets are per run, like the QMP socket
+    let mut substitutions = params.clone();
+    let mut sidecars = Vec::with_capacity(config.sidecars.len());
+    for spec in &config.sidecars {
+        let socket = instances_dir()?.join(format!(
+            "{}-{}-{}.sock",
+            name,
+            std::process::id(),
+            spec.name
+        ));
+        substitutions.insert(
+            format!("sidecar.{}", spec.name),
+            socket.display().to_string(),
+        );
+        sidecars.push((spec, socket));
+    }
+    let sidecars = sidecars
+        .into_iter()
+        .map(|(spec, socket)| {
+            let mut vars = substitutions.clone();
+            vars.insert("socket".to_string(), socket.display().to_string());
+            let command = substitute_params(&spec.command, &vars)
+                .with_context(|| format!("Failed to resolve command of sidecar '{}'", spec.name))?;
+            Ok(SidecarRun {
+                name: spec.name.clone(),
+                command,
+                socket,
+                timeout: spec.timeout,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let qemu_bin = substitute_value(&config.qemu_bin, &params)
+        .with_context(|| format!("Failed to resolve QEMU binary of configuration '{}'", name))?;
+
+    let mut exec_args = merge_args(&config.args, extra_args);
+
+    // Substitute parameters in args
+    exec_args = substitute_params(&exec_args, &substitutions)
+        .with_context(|| format!("Failed to resolve arguments of configuration '{}'", name))?;
+
+    let mut forwards = config.forwards.clone();
+    // `vex ssh` needs a forward to the guest's sshd
+    if config.ssh.is_some()
+        && !forwards
+            .iter()
+            .any(|f| f.proto == Protocol::Tcp && f.guest == SSH_GUEST_PORT)
+        && find_host_forward(&exec_args, SSH_GUEST_PORT).is_none()
+    {
+        forwards.push(PortForward {
+            proto: Protocol::Tcp,
+            host: None,
+            guest: SSH_GUEST_PORT,
+        });
+    }
+    let forwards = resolve_forwards(&forwards)?;
+    let hostfwds: Vec<String> = forwards.iter().filter_map(PortForward::hostfwd).collect();
+    add_host_forwards(&mut exec_args, &hostfwds);
+
+    let gdb_port = if debug {
+        let port = resolve_gdb_port(gdb_port.or(config.gdb_port))?;
+        // Saved GDB options would start a second server next to the one added here
+        let kept: Vec<_> = tokenize(&exec_args)
+            .into_iter()
+            .filter(|token| !token.is(GDB_OPTIONS))
+            .collect();
+        exec_args = untokenize(&kept);
+        // Add debug parameters, using an explicit port instead of `-s`
+        exec_args.push("-gdb".to_string());
+        exec_args.push(format!("tcp::{}", port));
+        exec_args.push("-S".to_string());
+        Some(port)
+    } else {
+        None
+    };
+
+    Ok(RunPlan {
+        name,
+        desc: config.desc,
+        qemu_version: config.qemu_version,
+        qemu_bin,
+        args: exec_args,
+        debug,
+        gdb_port,
+        forwards,
+        params,
+        pre_exec: config.pre_exec,
+        post_exec: config.post_exec,
+        sidecars,
+        cwd: config.cwd,
+    })
+}
+
+/// Fail early when the directory a configuration runs from has gone away
+fn check_cwd(name: &str, cwd: Option<&Path>) -> Result<()> {
+    if let Some(dir) = cwd
+        && !dir.is_dir()
+    {
+        anyhow::bail!(
+            "Working directory '{}' of configuration '{}' does not exist. Use '--cwd' to run from another directory",
+            dir.display(),
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Run QEMU in the foreground, forwarding signals and passing its exit status through.
+///
+/// Output is shown live and also written to a per-run log file. With a matcher,
+/// QEMU is terminated as soon as an `--expect`/`--fail-on` pattern matches.
+fn run_foreground(
+    launch: &Launch,
+    log: fs::File,
+    timeout: Option<Duration>,
+    mut matcher: Option<ExpectMatcher>,
+) -> Result<()> {
+    let mut child = launch
+        .command()
+        .args(&launch.args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to execute QEMU: {}", launch.qemu_bin))?;
+
+    launch.pid.set(Some(child.id()));
+    let instance = launch.instance(child.id(), false);
+    save_instance(&instance)?;
+    forward_signals_to(child.id());
+
+    let log = Arc::new(Mutex::new(log));
+    let (tx, rx) = mpsc::channel();
+    if let Some(out) = child.stdout.take() {
+        tee_output(out, io::stdout(), Arc::clone(&log), tx.clone());
+    }
+    if let Some(err) = child.stderr.take() {
+        tee_output(err, io::stderr(), log, tx);
+    }
+
+    let mut feed = |chunk: &str| matcher.as_mut().and_then(|m| m.feed(chunk));
+    // A limit too far in the future to represent never expires
+    let deadline = timeout.and_then(|limit| Instant::now().checked_add(limit));
+    let outcome = loop {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break ForegroundWait::TimedOut;
+        }
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(chunk) => {
+                if let Some(outcome) = feed(&chunk) {
+                    break ForegroundWait::Outcome(outcome);
+                }
+                continue;
             }
-            None => {
-                println!("WARNING: Could not detect current QEMU version.\n");
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => thread::sleep(Duration::from_millis(20)),
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Output written just before exit may still be in flight
+                let late_match =
+                    std::iter::from_fn(|| rx.recv_timeout(Duration::from_millis(200)).ok())
+                        .find_map(|chunk| feed(&chunk));
+                break match late_match {
+                    Some(outcome) => ForegroundWait::Outcome(outcome),
+                    None => ForegroundWait::Exited(status),
+                };
             }
-            _ => {} // Versions match, all good
+            Ok(None) => {}
+            Err(err) => break ForegroundWait::Failed(err),
+        }
+    };
+
+    let result = match outcome {
+        ForegroundWait::Outcome(ExpectOutcome::Matched(pattern)) => {
+            terminate_child(&mut child);
+            println!("\nvex: matched expected pattern '{}'", pattern);
+            Ok(())
         }
+        ForegroundWait::Outcome(ExpectOutcome::Failed(pattern)) => {
+            terminate_child(&mut child);
+            Err(VexError::Exit {
+                code: EXPECT_FAILED_EXIT_CODE,
+                message: format!("vex: matched failure pattern '{}'", pattern),
+            }
+            .into())
+        }
+        ForegroundWait::Exited(status) if matcher.as_ref().is_some_and(|m| m.has_expect()) => {
+            Err(VexError::Exit {
+                code: EXPECT_FAILED_EXIT_CODE,
+                message: format!(
+                    "vex: QEMU exited ({}) before any expected pattern matched",
+                    status
+                ),
+            }
+            .into())
+        }
+        ForegroundWait::Exited(status) => check_exit_status(status),
+        ForegroundWait::TimedOut => {
+            terminate_child(&mut child);
+            let limit = format_duration(timeout.unwrap_or_default().as_secs());
+            let message = if matcher.is_some() {
+                format!("vex: no pattern matched within {}", limit)
+            } else {
+                format!("QEMU timed out after {} and was terminated", limit)
+            };
+            Err(VexError::Exit {
+                code: TIMEOUT_EXIT_CODE,
+                message,
+            }
+            .into())
+        }
+        ForegroundWait::Failed(err) => {
+            terminate_child(&mut child);
+            Err(anyhow::Error::from(err).context("Failed to wait for QEMU"))
+        }
+    };
+
+    stop_forwarding_signals();
+    remove_instance(&instance)?;
+    result
+}
+
+/// Turn a failed QEMU exit status into an error carrying the same exit code
+fn check_exit_status(status: ExitStatus) -> Result<()> {
+    if status.success() {
+        return Ok(());
     }
-    let mut exec_args = config.args.clone();
+    let code = exit_code(status);
+    let message = match status.signal() {
+        Some(signal) => format!("QEMU was killed by signal {}", signal),
+        None => format!("QEMU execution failed with exit code: {}", code),
+    };
+    Err(VexError::Exit { code, message }.into())
+}
 
-    // Substitute parameters in args
-    exec_args = substitute_params(&exec_args);
+/// How waiting for a foreground QEMU ended
+enum ForegroundWait {
+    /// An `--expect` or `--fail-on` pattern matched
+    Outcome(ExpectOutcome),
+    Exited(ExitStatus),
+    TimedOut,
+    Failed(io::Error),
+}
 
-    if debug {
-        // Add debug parameters
-        exec_args.push("-s".to_string());
-        exec_args.push("-S".to_string());
+/// Copy a child's output stream to `sink` and the run log, passing each chunk to `tx`.
+///
+/// The reader thread is not joined, since a process started by QEMU could
+/// keep the pipe open after QEMU itself is gone.
+fn tee_output<R, W>(mut source: R, mut sink: W, log: Arc<Mutex<fs::File>>, tx: mpsc::Sender<String>)
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = source.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let _ = sink.write_all(&buf[..n]);
+            let _ = sink.flush();
+            if let Ok(mut log) = log.lock() {
+                let _ = log.write_all(&buf[..n]);
+            }
+            // The receiver is gone once vex stopped waiting; keep draining the pipe
+            let _ = tx.send(String::from_utf8_lossy(&buf[..n]).into_owned());
+        }
+    });
+}
+
+/// Ask a child to terminate, killing it if it doesn't exit within a few seconds
+fn terminate_child(child: &mut Child) {
+    let _ = send_signal(child.id(), libc::SIGTERM);
+    if !matches!(wait_timeout(child, Duration::from_secs(5)), Ok(Some(_))) {
+        let _ = child.kill();
+        let _ = child.wait();
     }
+}
 
-    // Print startup message
-    print_startup_message(&name, &config, &exec_args, debug, full);
+/// Everything needed to start QEMU for one run
+struct Launch<'a> {
+    name: &'a str,
+    qemu_bin: &'a str,
+    args: Vec<String>,
+    gdb_port: Option<u16>,
+    sockets: BTreeMap<String, PathBuf>,
+    post_exec: &'a [String],
+    hook_env: &'a BTreeMap<String, String>,
+    /// Pids of the running sidecars, keyed by name
+    sidecars: &'a BTreeMap<String, u32>,
+    /// Run log receiving QEMU's output
+    log_path: PathBuf,
+    cwd: Option<&'a Path>,
+    /// Pid of QEMU once it was started
+    pid: Cell<Option<u32>>,
+}
+
+impl Launch<'_> {
+    /// QEMU command running from the configuration's working directory
+    fn command(&self) -> Command {
+        let mut command = Command::new(self.qemu_bin);
+        if let Some(dir) = self.cwd {
+            command.current_dir(dir);
+        }
+        command
+    }
 
-    let status = Command::new(&config.qemu_bin)
-        .args(&exec_args)
-        .status()
-        .with_context(|| format!("Failed to execute QEMU: {}", config.qemu_bin))?;
+    /// Build the instance record for the started QEMU process
+    fn instance(&self, pid: u32, detached: bool) -> Instance {
+        Instance {
+            id: Instance::make_id(self.name, pid),
+            config: self.name.to_string(),
+            pid,
+            start_time: process_start_time(pid),
+            qemu_bin: self.qemu_bin.to_string(),
+            args: self.args.clone(),
+            started_at: unix_now(),
+            log_file: Some(self.log_path.clone()),
+            detached,
+            gdb_port: self.gdb_port,
+            sockets: self.sockets.clone(),
+            // Foreground runs execute their post-exec hooks themselves
+            post_exec: if detached {
+                self.post_exec.to_vec()
+            } else {
+                Vec::new()
+            },
+            hook_env: if detached {
+                self.hook_env.clone()
+            } else {
+                BTreeMap::new()
+            },
+            // Foreground runs stop their sidecars themselves
+            sidecars: if detached {
+                self.sidecars.clone()
+            } else {
+                BTreeMap::new()
+            },
+            sidecar_start_times: if detached {
+                self.sidecars
+                    .iter()
+                    .filter_map(|(name, &pid)| Some((name.clone(), process_start_time(pid)?)))
+                    .collect()
+            } else {
+                BTreeMap::new()
+            },
+            cwd: self.cwd.map(Path::to_path_buf),
+        }
+    }
+}
+
+/// Start QEMU in the background with output redirected to a log file and record the instance
+fn spawn_detached(launch: &Launch, log: fs::File) -> Result<()> {
+    let log_err = log
+        .try_clone()
+        .context("Failed to create instance log file")?;
 
-    if !status.success() {
+    let mut child = launch
+        .command()
+        .args(&launch.args)
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(log_err)
+        // Own process group, so terminal signals to vex don't reach QEMU
+        .process_group(0)
+        .spawn()
+        .with_context(|| format!("Failed to execute QEMU: {}", launch.qemu_bin))?;
+
+    let pid = child.id();
+    launch.pid.set(Some(pid));
+    let instance = launch.instance(pid, true);
+
+    // Catch immediate failures such as bad arguments before reporting success
+    thread::sleep(Duration::from_millis(200));
+    if let Some(status) = child.try_wait().context("Failed to check QEMU process")? {
+        remove_instance(&instance)?;
         anyhow::bail!(
-            "QEMU execution failed with exit code: {}",
-            status.code().unwrap_or(-1)
+            "QEMU exited immediately with exit code: {}. See log: {}",
+            status.code().unwrap_or(-1),
+            launch.log_path.display()
         );
     }
 
+    save_instance(&instance)?;
+
+    println!("Started instance '{}' (pid {})", instance.id, pid);
+    println!("  Logs: vex logs {}", instance.id);
+    println!("  Stop: vex stop {}", instance.id);
+
     Ok(())
 }
 
 /// Print a user-friendly startup message
-fn print_startup_message(
-    name: &str,
-    config: &QemuConfig,
-    args: &[String],
-    debug: bool,
-    full: bool,
-) {
+fn print_startup_message(plan: &RunPlan, args: &[String], full: bool) {
     // Build the header
-    let header = if let Some(desc) = &config.desc {
-        format!("Starting configuration '{}' ({})", name, desc)
+    let header = if let Some(desc) = &plan.desc {
+        format!("Starting configuration '{}' ({})", plan.name, desc)
     } else {
-        format!("Starting configuration '{}'", name)
+        format!("Starting configuration '{}'", plan.name)
     };
 
     println!("{}", header);
 
     // Show full command if -f flag is used
     if full {
-        println!("  QEMU: {}", config.qemu_bin);
-        println!("  Args: {:?}", args);
+        println!("  QEMU: {}", plan.qemu_bin);
+        if let Some(dir) = &plan.cwd {
+            println!("  Working directory: {}", dir.display());
+        }
+        println!(
+            "  Command: {}",
+            shell_join(std::iter::once(&plan.qemu_bin).chain(args))
+        );
+    }
+
+    for forward in &plan.forwards {
+        if let Some(host) = forward.host {
+            println!(
+                "  Forward: {} localhost:{} -> guest:{}",
+                forward.proto, host, forward.guest
+            );
+        }
     }
 
     // Show debug info if in debug mode
-    if debug {
+    if let Some(port) = plan.gdb_port {
         println!("  Mode: DEBUG");
-        println!("  GDB server: localhost:1234");
-        println!("\n💡 You can connect with: gdb -ex 'target remote localhost:1234'");
+        println!("  GDB server: localhost:{}", port);
+        println!(
+            "\n💡 You can connect with: gdb -ex 'target remote localhost:{}'",
+            port
+        );
     }
 }
 
-/// Substitute parameters in arguments using regex
-pub(crate) fn substitute_params(args: &[String]) -> Vec<String> {
-    let re = Regex::new(r"\$\{([^}]+)\}").unwrap();
+/// Pick the GDB server port for debug mode.
+///
+/// A fixed port must be free. Without any setting, port 1234 is preferred
+/// and a free port is chosen when it is already taken.
+pub(crate) fn resolve_gdb_port(requested: Option<GdbPort>) -> Result<u16> {
+    match requested {
+        Some(GdbPort::Fixed(port)) => {
+            if !is_port_free(port) {
+                anyhow::bail!(
+                    "GDB port {} is already in use. Choose another with '--gdb-port' or use '--gdb-port auto'",
+                    port
+                );
+            }
+            Ok(port)
+        }
+        Some(GdbPort::Auto) => find_free_port(),
+        None if is_port_free(DEFAULT_GDB_PORT) => Ok(DEFAULT_GDB_PORT),
+        None => {
+            let port = find_free_port()?;
+            eprintln!(
+                "NOTE: GDB port {} is in use, using free port {} instead",
+                DEFAULT_GDB_PORT, port
+            );
+            Ok(port)
+        }
+    }
+}
+
+/// Check that the host ports recorded in a replayed run are still free, since QEMU
+/// would otherwise fail to bind them with a less helpful error
+fn check_replayed_ports(plan: &RunPlan, run_id: u64) -> Result<()> {
+    for (proto, addr, port) in host_forward_ports(&plan.args) {
+        if !is_addr_port_free(proto, &addr, port) {
+            anyhow::bail!(
+                "Host port {}/{} forwarded by run #{} is already in use. Free it or run '{}' again to pick new ports",
+                port,
+                proto,
+                run_id,
+                plan.name
+            );
+        }
+    }
+    if let Some(port) = plan.gdb_port
+        && !is_port_free(port)
+    {
+        anyhow::bail!(
+            "GDB port {} used by run #{} is already in use. Free it or run '{}' again to pick a new port",
+            port,
+            run_id,
+            plan.name
+        );
+    }
+    Ok(())
+}
+
+/// Pick host ports for port forwards: fixed ports must be free, `auto` gets a free one
+pub(crate) fn resolve_forwards(forwards: &[PortForward]) -> Result<Vec<PortForward>> {
+    let mut resolved: Vec<PortForward> = Vec::with_capacity(forwards.len());
+    let taken = |resolved: &[PortForward], proto: Protocol, port: u16| {
+        resolved
+            .iter()
+            .any(|other| other.proto == proto && other.host == Some(port))
+    };
+
+    for forward in forwards {
+        let host = match forward.host {
+            Some(port) => {
+                if taken(&resolved, forward.proto, port) {
+                    anyhow::bail!("Host port {} is forwarded more than once", port);
+                }
+                if !is_proto_port_free(forward.proto, port) {
+                    anyhow::bail!(
+                        "Host port {} for forward '{}' is already in use. Choose another port or use 'auto'",
+                        port,
+                        forward
+                    );
+                }
+                port
+            }
+            None => loop {
+                let port = find_free_proto_port(forward.proto)?;
+                if !taken(&resolved, forward.proto, port) {
+                    break port;
+                }
+            },
+        };
+        resolved.push(PortForward {
+            host: Some(host),
+            ..*forward
+        });
+    }
+
+    Ok(resolved)
+}
+
+/// Substitute parameters in arguments using regex, looking names up in
+/// declared `params` before the environment
+pub(crate) fn substitute_params(
+    args: &[String],
+    params: &BTreeMap<String, String>,
+) -> Result<Vec<String>> {
     args.iter()
-        .map(|arg| {
-            re.replace_all(arg, |caps: &regex::Captures| {
-                std::env::var(&caps[1]).unwrap_or_else(|_| format!("${{{}}}", &caps[1]))
-            })
-            .to_string()
-        })
+        .map(|arg| substitute_value(arg, params))
         .collect()
 }
+
+/// Names of the variables referenced by `values`, skipping `$${...}` escapes
+pub(crate) fn referenced_variables<'a>(
+    values: impl IntoIterator<Item = &'a String>,
+) -> BTreeSet<String> {
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
+    values
+        .into_iter()
+        .flat_map(|value| re.captures_iter(value))
+        .filter(|caps| caps.get(1).is_none())
+        .map(|caps| caps[2].to_string())
+        .collect()
+}
+
+/// Expand variables in a single value, looking names up in `params` first.
+///
+/// Supports `${VAR}` (left as-is when unset), `${VAR:-default}` (default when
+/// unset or empty), `${VAR:?message}` (error when unset or empty) and `$${...}`
+/// for a literal `${...}`.
+pub(crate) fn substitute_value(value: &str, params: &BTreeMap<String, String>) -> Result<String> {
+    let re = Regex::new(VARIABLE_PATTERN).unwrap();
+    let mut result = String::with_capacity(value.len());
+    let mut last = 0;
+
+    for caps in re.captures_iter(value) {
+        let whole = caps.get(0).unwrap();
+        result.push_str(&value[last..whole.start()]);
+        last = whole.end();
+
+        // `$${...}` is an escape: drop one dollar and keep the rest verbatim
+        if caps.get(1).is_some() {
+            result.push_str(&whole.as_str()[1..]);
+            continue;
+        }
+
+        let var = &caps[2];
+        let current = params.get(var).cloned().or_else(|| std::env::var(var).ok());
+        match (caps.get(3).map(|m| m.as_str()), current) {
+            (None, Some(val)) => result.push_str(&val),
+            (None, None) => result.push_str(whole.as_str()),
+            (Some(_), Some(val)) if !val.is_empty() => result.push_str(&val),
+            (Some("-"), _) => result.push_str(&caps[4]),
+            (_, _) => {
+                let message = match &caps[4] {
+                    "" => "parameter null or not set",
+                    msg => msg,
+                };
+                anyhow::bail!("Variable '{}' is required: {}", var, message);
+            }
+        }
+    }
+
+    result.push_str(&value[last..]);
+    Ok(result)
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::instance::load_history;
+use crate::utils::shell::shell_join;
+use crate::utils::time::{format_duration, format_timestamp};
+
+#[derive(Args)]
+#[clap(about = "Show past 'vex exec' runs")]
+pub struct HistoryArgs {
+    #[arg(help = "Only show runs of this configuration")]
+    pub name: Option<String>,
+
+    #[arg(
+        short = 'f',
+        long = "full",
+        help = "Show the full QEMU command of each run"
+    )]
+    pub full: bool,
+
+    #[arg(short = 'n', long = "limit", help = "Only show the last N runs")]
+    pub limit: Option<usize>,
+}
+
+pub fn history_command(name: Option<String>, full: bool, limit: Option<usize>) -> Result<()> {
+    let records: Vec<_> = load_history()?
+        .into_iter()
+        .filter(|record| name.as_ref().is_none_or(|name| record.config == *name))
+        .collect();
+
+    if records.is_empty() {
+        match name {
+            Some(name) => println!("No runs of '{}' recorded.", name),
+            None => println!("No runs recorded."),
+        }
+        return Ok(());
+    }
+
+    let skip = limit.map_or(0, |n| records.len().saturating_sub(n));
+
+    println!(
+        "{:>5}  {:<16} {:<20} {:>8}  {:<10} DEBUG",
+        "ID", "CONFIG", "STARTED (UTC)", "DURATION", "STATUS"
+    );
+    for record in &records[skip..] {
+        let duration = record
+            .duration_secs
+            .map(format_duration)
+            .unwrap_or_else(|| "-".to_string());
+        let status = match (record.detached, record.exit_code) {
+            (true, _) => "detached".to_string(),
+            (false, Some(0)) => "ok".to_string(),
+            (false, Some(code)) => format!("exit {}", code),
+            (false, None) => "error".to_string(),
+        };
+        let debug = match record.gdb_port {
+            Some(port) => format!("yes (:{})", port),
+            None if record.debug => "yes".to_string(),
+            None => "no".to_string(),
+        };
+        println!(
+            "{:>5}  {:<16} {:<20} {:>8}  {:<10} {}",
+            record.id,
+            record.config,
+            format_timestamp(record.started_at),
+            duration,
+            status,
+            debug
+        );
+        if full {
+            println!(
+                "       {}",
+                shell_join(std::iter::once(&record.qemu_bin).chain(&record.args))
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use clap::Args;
+use std::time::Duration;
+
+use crate::commands::stop::finish_exited_instances;
+use crate::instance::{find_instance, find_running_instance, finish_instance};
+use crate::utils::process::wait_for_exit;
+
+#[derive(Args)]
+#[clap(about = "Kill a running QEMU instance immediately")]
+pub struct KillArgs {
+    #[arg(help = "Instance ID or configuration name")]
+    pub instance: String,
+}
+
+pub fn kill_command(query: String) -> Result<()> {
+    let exited = finish_exited_instances(&query)?;
+    let instance = match find_running_instance(&query)? {
+        Some(instance) => instance,
+        None if exited => return Ok(()),
+        None => find_instance(&query)?,
+    };
+
+    instance.signal(libc::SIGKILL)?;
+    if !wait_for_exit(instance.pid, Duration::from_secs(5)) {
+        anyhow::bail!(
+            "Instance '{}' (pid {}) is still running after SIGKILL",
+            instance.id,
+            instance.pid
+        );
+    }
+
+    println!("Instance '{}' killed", instance.id);
+    finish_instance(&instance)?;
+    Ok(())
+}
@@ -1,8 +1,9 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap::Args;
-use std::fs;
 
-use crate::config::{QemuConfig, config_dir};
+use crate::config::{
+    ConfigFormat, config_dir, config_files, config_names, file_names, read_config,
+};
 
 #[derive(Args)]
 #[clap(about = "List all saved QEMU configurations")]
@@ -15,43 +16,60 @@ pub fn list_command() -> Result<()> {
         return Ok(());
     }
 
-    let entries = fs::read_dir(&dir).context("Failed to read config directory")?;
     let mut configs = Vec::new();
+    let mut duplicates = Vec::new();
 
-    for entry in entries {
-        let entry = entry.context("Failed to read directory entry")?;
-        let path = entry.path();
-        if path.extension().is_some_and(|ext| ext == "json")
-            && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+    for name in config_names()? {
+        let files = config_files(&name)?;
+        if files.len() > 1 {
+            duplicates.push((name, files));
+            continue;
+        }
+        // Skip invalid config files
+        if let Some(path) = files.first()
+            && let Ok(config) = read_config(&name, path)
         {
-            match fs::read_to_string(&path) {
-                Ok(content) => {
-                    if let Ok(config) = serde_json::from_str::<QemuConfig>(&content) {
-                        configs.push((name.to_string(), config));
-                    }
-                }
-                Err(_) => {
-                    // Skip invalid config files
-                    continue;
-                }
-            }
+            configs.push((name, ConfigFormat::from_path(path), config));
         }
     }
 
-    if configs.is_empty() {
+    if configs.is_empty() && duplicates.is_empty() {
         println!("No configurations found.");
     } else {
         println!("Saved configurations:");
-        for (name, config) in configs {
+        for (name, format, config) in configs {
             if let Some(desc) = config.desc {
                 println!("  {} - {}", name, desc);
             } else {
                 println!("  {} - (no description)", name);
             }
-            println!("    QEMU: {}", config.qemu_bin);
+            if let Some(format) = format.filter(|format| *format != ConfigFormat::Json) {
+                println!("    Format: {}", format);
+            }
+            if let Some(parent) = &config.extends {
+                println!("    Extends: {}", parent);
+            }
+            if !config.qemu_bin.is_empty() {
+                println!("    QEMU: {}", config.qemu_bin);
+            }
             println!("    Args: {:?}", config.args);
             println!();
         }
+        for (name, files) in &duplicates {
+            println!(
+                "  {} - (saved in several formats: {})",
+                name,
+                file_names(files)
+            );
+            println!();
+        }
+    }
+
+    for (name, _) in &duplicates {
+        eprintln!(
+            "WARNING: '{}' exists in several formats and can't be loaded until only one is left",
+            name
+        );
     }
 
     Ok(())
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::instance::{
+    Instance, find_instance_run, list_instances, list_run_logs, run_log_started,
+};
+use crate::utils::process::is_process_alive;
+
+#[derive(Args)]
+#[clap(about = "Show QEMU output logs of current and past runs")]
+pub struct LogsArgs {
+    #[arg(help = "Configuration name or instance ID")]
+    pub name: String,
+
+    #[arg(
+        short = 'r',
+        long = "run",
+        value_name = "N",
+        help = "Show the N-th most recent run (1 = latest)"
+    )]
+    pub run: Option<usize>,
+
+    #[arg(
+        short = 'l',
+        long = "list",
+        conflicts_with_all = ["run", "follow", "lines"],
+        help = "List the recorded runs instead of showing a log"
+    )]
+    pub list: bool,
+
+    #[arg(
+        short = 'f',
+        long = "follow",
+        help = "Keep printing new output while the run is still going"
+    )]
+    pub follow: bool,
+
+    #[arg(short = 'n', long = "lines", help = "Only show the last N lines")]
+    pub lines: Option<usize>,
+}
+
+pub fn logs_command(
+    name: String,
+    run: Option<usize>,
+    list: bool,
+    follow: bool,
+    lines: Option<usize>,
+) -> Result<()> {
+    let running: Vec<Instance> = list_instances()?
+        .into_iter()
+        .filter(|i| (i.id == name || i.config == name) && i.is_running())
+        .collect();
+    let (config, instance_log) = match running.as_slice() {
+        [instance] => (instance.config.clone(), instance.log_file.clone()),
+        // The run history remembers the log of an instance that has exited
+        [] => match find_instance_run(&name)? {
+            Some(record) => (record.config, record.log_file),
+            None => (name.clone(), None),
+        },
+        // `--run` picks a log rather than an instance, so only following is ambiguous
+        _ if follow && run.is_none() => {
+            let ids: Vec<&str> = running.iter().map(|i| i.id.as_str()).collect();
+            anyhow::bail!(
+                "Configuration '{}' has several running instances ({}), specify an instance ID or '--run N' to follow",
+                name,
+                ids.join(", ")
+            )
+        }
+        _ => (name.clone(), None),
+    };
+
+    let logs = list_run_logs(&config)?;
+    if logs.is_empty() {
+        anyhow::bail!("No logs found for '{}'", name);
+    }
+
+    if list {
+        print_runs(&config, &logs)?;
+        return Ok(());
+    }
+
+    let log_file = match (run, instance_log) {
+        (Some(0), _) => anyhow::bail!("Run numbers start at 1 (the latest run)"),
+        (Some(n), _) => logs
+            .iter()
+            .rev()
+            .nth(n - 1)
+            .cloned()
+            .with_context(|| format!("Only {} run(s) recorded for '{}'", logs.len(), config))?,
+        (None, Some(path)) if path.exists() => path,
+        (None, Some(_)) => anyhow::bail!(
+            "The log of instance '{}' was deleted to keep only the latest runs",
+            name
+        ),
+        (None, None) => logs[logs.len() - 1].clone(),
+    };
+
+    let mut file = fs::File::open(&log_file)
+        .with_context(|| format!("Failed to open log file {}", log_file.display()))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .context("Failed to read log file")?;
+
+    let shown = match lines {
+        Some(n) => {
+            let all: Vec<&str> = content.lines().collect();
+            let start = all.len().saturating_sub(n);
+            all[start..].join("\n") + if all.is_empty() { "" } else { "\n" }
+        }
+        None => content,
+    };
+    print!("{}", shown);
+
+    if follow && let Some(pid) = writer_pid(&log_file)? {
+        follow_log(&mut file, pid)?;
+    }
+
+    Ok(())
+}
+
+/// Print the recorded runs of a configuration, latest first
+fn print_runs(config: &str, logs: &[PathBuf]) -> Result<()> {
+    println!("Runs of '{}':", config);
+    for (i, path) in logs.iter().rev().enumerate() {
+        let started = run_log_started(path).unwrap_or_else(|| "unknown".to_string());
+        let state = if writer_pid(path)?.is_some() {
+            " (running)"
+        } else {
+            ""
+        };
+        println!(
+            "  #{:<3} {} UTC{}  {}",
+            i + 1,
+            started,
+            state,
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Pid of the running QEMU instance still writing to a log file
+fn writer_pid(log_file: &PathBuf) -> Result<Option<u32>> {
+    Ok(list_instances()?
+        .into_iter()
+        .find(|i| i.log_file.as_ref() == Some(log_file) && i.is_running())
+        .map(|i| i.pid))
+}
+
+/// Print data appended to the log until the process exits
+fn follow_log(file: &mut fs::File, pid: u32) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    let mut buf = Vec::new();
+    loop {
+        let alive = is_process_alive(pid);
+        buf.clear();
+        file.read_to_end(&mut buf)
+            .context("Failed to read log file")?;
+        if !buf.is_empty() {
+            stdout.write_all(&buf)?;
+            stdout.flush()?;
+        }
+        if !alive {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
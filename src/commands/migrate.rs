@@ -0,0 +1,68 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::config::{SCHEMA_VERSION, config_file, config_names, migrate_config_file};
+
+#[derive(Args)]
+#[clap(about = "Upgrade saved configurations to the current schema version")]
+pub struct MigrateArgs {
+    #[arg(
+        help = "Configuration names to migrate",
+        required_unless_present = "all",
+        conflicts_with = "all"
+    )]
+    pub names: Vec<String>,
+
+    #[arg(short = 'a', long = "all", help = "Migrate every saved configuration")]
+    pub all: bool,
+}
+
+pub fn migrate_command(names: Vec<String>, all: bool) -> Result<()> {
+    if !all {
+        for name in names {
+            let config_path = config_file(&name)?;
+            if !config_path.exists() {
+                anyhow::bail!(
+                    "Configuration '{}' does not exist. Use 'vex list' to see available configurations",
+                    name
+                );
+            }
+            migrate_one(&name)?;
+        }
+        return Ok(());
+    }
+
+    let all_names = config_names()?;
+
+    // Keep going past files that can't be migrated, then report them together
+    let mut failed = 0;
+    for name in &all_names {
+        if let Err(err) = migrate_one(name) {
+            eprintln!("Skipped '{}': {:#}", name, err);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} configurations could not be migrated",
+            failed,
+            all_names.len()
+        );
+    }
+    Ok(())
+}
+
+fn migrate_one(name: &str) -> Result<()> {
+    let config_path = config_file(name)?;
+    match migrate_config_file(name, &config_path)? {
+        Some((version, backup)) => println!(
+            "Migrated '{}' from schema version {} to {} (backup: {})",
+            name,
+            version,
+            SCHEMA_VERSION,
+            backup.display()
+        ),
+        None => println!("'{}' is already at schema version {}", name, SCHEMA_VERSION),
+    }
+    Ok(())
+}
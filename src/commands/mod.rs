@@ -1,18 +1,44 @@
 pub mod completions;
+pub mod convert;
+pub mod down;
+pub mod edit;
 pub mod exec;
+pub mod history;
+pub mod kill;
 pub mod list;
+pub mod logs;
+pub mod migrate;
 pub mod print;
+pub mod ps;
+pub mod qmp;
 pub mod remove;
 pub mod rename;
 pub mod save;
+pub mod snapshot;
+pub mod ssh;
+pub mod stop;
+pub mod up;
 
 pub use completions::{CompletionsArgs, completions_command};
+pub use convert::{ConvertArgs, convert_command};
+pub use down::{DownArgs, down_command};
+pub use edit::{EditArgs, edit_command};
 pub use exec::{ExecArgs, exec_command};
+pub use history::{HistoryArgs, history_command};
+pub use kill::{KillArgs, kill_command};
 pub use list::{ListArgs, list_command};
+pub use logs::{LogsArgs, logs_command};
+pub use migrate::{MigrateArgs, migrate_command};
 pub use print::{PrintArgs, print_command};
+pub use ps::{PsArgs, ps_command};
+pub use qmp::{QmpArgs, qmp_command};
 pub use remove::{RemoveArgs, remove_command};
 pub use rename::{RenameArgs, rename_command};
 pub use save::{SaveArgs, save_command};
+pub use snapshot::{SnapshotArgs, snapshot_command};
+pub use ssh::{SshArgs, ssh_command};
+pub use stop::{StopArgs, stop_command};
+pub use up::{UpArgs, up_command};
 
 use clap::{Parser, Subcommand};
 
@@ -23,7 +49,20 @@ pub enum Commands {
     Rm(RemoveArgs),
     List(ListArgs),
     Print(PrintArgs),
+    Edit(EditArgs),
+    Migrate(MigrateArgs),
+    Convert(ConvertArgs),
     Exec(ExecArgs),
+    History(HistoryArgs),
+    Ps(PsArgs),
+    Stop(StopArgs),
+    Kill(KillArgs),
+    Logs(LogsArgs),
+    Ssh(SshArgs),
+    Qmp(QmpArgs),
+    Snapshot(SnapshotArgs),
+    Up(UpArgs),
+    Down(DownArgs),
     Completions(CompletionsArgs),
 }
 
@@ -1,28 +1,57 @@
 use anyhow::{Context, Result};
 use clap::Args;
-use std::fs;
+use std::path::PathBuf;
 
-use crate::config::{QemuConfig, config_file};
+use crate::commands::exec::{referenced_variables, substitute_params, substitute_value};
+use crate::config::params::parse_set;
+use crate::config::{
+    Inherited, QemuConfig, config_env_file, config_file, load_env_files, load_inherited,
+    merge_variables, resolve_params,
+};
+use crate::utils::shell::shell_join;
 
 #[derive(Args)]
 #[clap(about = "Print detailed information about a saved QEMU configuration")]
 pub struct PrintArgs {
     #[arg(help = "Configuration name to print")]
     pub name: String,
+
+    #[arg(
+        long = "resolved",
+        help = "Also show variable values and the command after substitution"
+    )]
+    pub resolved: bool,
+
+    #[arg(
+        long = "env-file",
+        value_name = "FILE",
+        requires = "resolved",
+        help = "Env file to resolve with, as for 'vex exec --env-file' (repeatable)"
+    )]
+    pub env_files: Vec<PathBuf>,
+
+    #[arg(
+        long = "set",
+        value_name = "NAME=VALUE",
+        value_parser = parse_set,
+        requires = "resolved",
+        help = "Parameter value to resolve with, as for 'vex exec --set' (repeatable)"
+    )]
+    pub sets: Vec<(String, String)>,
 }
 
-pub fn print_command(name: String) -> Result<()> {
+pub fn print_command(
+    name: String,
+    resolved: bool,
+    env_files: Vec<PathBuf>,
+    sets: Vec<(String, String)>,
+) -> Result<()> {
     let config_path = config_file(&name)?;
-    if !config_path.exists() {
-        anyhow::bail!(
-            "Configuration '{}' does not exist. Use 'vex list' to see available configurations",
-            name
-        );
-    }
-
-    let config_json = fs::read_to_string(&config_path).context("Failed to read config file")?;
-    let config: QemuConfig =
-        serde_json::from_str(&config_json).context("Failed to deserialize configuration")?;
+    let Inherited {
+        config,
+        chain,
+        args: inherited_args,
+    } = load_inherited(&name)?;
 
     // Print configuration details
     println!("Configuration: {}", name);
@@ -36,15 +65,102 @@ pub fn print_command(name: String) -> Result<()> {
         println!();
     }
 
+    // Print the inheritance chain, nearest parent first
+    if chain.len() > 1 {
+        println!("Extends:");
+        println!("  {}", chain.join(" -> "));
+        println!();
+    }
+
     // Print QEMU binary
     println!("QEMU Binary:");
     println!("  {}", config.qemu_bin);
     println!();
 
+    // Print working directory
+    if let Some(cwd) = &config.cwd {
+        println!("Working Directory:");
+        println!("  {}", cwd.display());
+        println!();
+    }
+
+    // Print default GDB port if configured
+    if let Some(port) = &config.gdb_port {
+        println!("GDB Port:");
+        println!("  {}", port);
+        println!();
+    }
+
+    // Print declared parameters
+    if !config.params.is_empty() {
+        println!("Parameters:");
+        for (name, spec) in &config.params {
+            let default = match &spec.default {
+                Some(default) => format!(" = {}", default),
+                None => " (required)".to_string(),
+            };
+            println!("  {} <{}>{}", name, spec.kind, default);
+            if let Some(help) = &spec.help {
+                println!("      {}", help);
+            }
+        }
+        println!();
+    }
+
+    // Print port forwards
+    if !config.forwards.is_empty() {
+        println!("Port Forwards:");
+        for forward in &config.forwards {
+            println!("  {}", forward);
+        }
+        println!();
+    }
+
+    // Print login settings for `vex ssh`
+    if let Some(ssh) = &config.ssh {
+        println!("SSH:");
+        println!("  User: {}", ssh.user.as_deref().unwrap_or("(ssh default)"));
+        if let Some(key) = &ssh.key {
+            println!("  Key: {}", key);
+        }
+        println!();
+    }
+
+    // Print helper daemons
+    if !config.sidecars.is_empty() {
+        println!("Sidecars:");
+        for sidecar in &config.sidecars {
+            println!("  {}: {}", sidecar.name, shell_join(&sidecar.command));
+        }
+        println!();
+    }
+
+    // Print hooks run around QEMU
+    if !config.pre_exec.is_empty() || !config.post_exec.is_empty() {
+        println!("Hooks:");
+        for hook in &config.pre_exec {
+            println!("  pre-exec:  {}", hook);
+        }
+        for hook in &config.post_exec {
+            println!("  post-exec: {}", hook);
+        }
+        println!();
+    }
+
     // Print startup arguments
     println!("Startup Arguments:");
     if config.args.is_empty() {
         println!("  (no arguments)");
+    } else if chain.len() > 1 {
+        // Merged arguments, each with the configuration it came from
+        for (i, item) in inherited_args.iter().enumerate() {
+            println!(
+                "  [{}] {}  ({})",
+                i,
+                shell_join(item.arg.to_argv()),
+                item.source
+            );
+        }
     } else {
         for (i, arg) in config.args.iter().enumerate() {
             println!("  [{}] {}", i, arg);
@@ -54,13 +170,72 @@ pub fn print_command(name: String) -> Result<()> {
 
     // Print full command line
     println!("Full Command:");
-    let full_command = format!("{} {}", config.qemu_bin, config.args.join(" "));
+    let full_command = shell_join(std::iter::once(&config.qemu_bin).chain(&config.args));
     println!("  {}", full_command);
     println!();
 
+    if resolved {
+        print_resolved(&name, &config, &env_files, &sets)?;
+    }
+
     // Print configuration file location
     println!("Configuration File:");
     println!("  {:?}", config_path);
 
     Ok(())
 }
+
+/// Print every referenced variable with its value and origin, then the substituted command
+fn print_resolved(
+    name: &str,
+    config: &QemuConfig,
+    env_files: &[PathBuf],
+    sets: &[(String, String)],
+) -> Result<()> {
+    let params = resolve_params(config, sets)
+        .with_context(|| format!("Invalid parameters for configuration '{}'", name))?;
+    let file_vars = load_env_files(name, env_files)?;
+    let vars = merge_variables(&params, &file_vars);
+
+    let own_env_file = config_env_file(name)?;
+    if own_env_file.exists() {
+        println!("Env File:");
+        println!("  {:?}", own_env_file);
+        println!();
+    }
+
+    println!("Resolved Variables:");
+    let names = referenced_variables(std::iter::once(&config.qemu_bin).chain(&config.args));
+    if names.is_empty() {
+        println!("  (no variables)");
+    }
+    for var in &names {
+        let (value, source) = if let Some(value) = params.get(var) {
+            (Some(value.clone()), "parameter".to_string())
+        } else if let Ok(value) = std::env::var(var) {
+            (Some(value), "environment".to_string())
+        } else if let Some(file_var) = file_vars.get(var) {
+            (
+                Some(file_var.value.clone()),
+                format!("env file {}", file_var.source.display()),
+            )
+        } else if var.starts_with("sidecar.") {
+            (None, "sidecar socket, set at launch".to_string())
+        } else {
+            (None, "unset".to_string())
+        };
+        match value {
+            Some(value) => println!("  {} = {}  ({})", var, value, source),
+            None => println!("  {}  ({})", var, source),
+        }
+    }
+    println!();
+
+    let qemu_bin = substitute_value(&config.qemu_bin, &vars)?;
+    let args = substitute_params(&config.args, &vars)?;
+    println!("Resolved Command:");
+    println!("  {}", shell_join(std::iter::once(&qemu_bin).chain(&args)));
+    println!();
+
+    Ok(())
+}
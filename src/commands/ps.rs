@@ -0,0 +1,51 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::instance::{list_instances, prune_stale_instances};
+use crate::utils::time::format_duration;
+
+#[derive(Args)]
+#[clap(about = "List running QEMU instances started with 'vex exec'")]
+pub struct PsArgs;
+
+pub fn ps_command() -> Result<()> {
+    for stale in prune_stale_instances(None)? {
+        println!(
+            "Removed stale instance '{}' (process {} is no longer running)",
+            stale.id, stale.pid
+        );
+    }
+
+    let instances = list_instances()?;
+    if instances.is_empty() {
+        println!("No running instances.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<24} {:<16} {:>8} {:>8}  {:<10} GDB",
+        "INSTANCE", "CONFIG", "PID", "UPTIME", "MODE"
+    );
+    for instance in instances {
+        let gdb = instance
+            .gdb_port
+            .map(|port| format!("localhost:{}", port))
+            .unwrap_or_else(|| "-".to_string());
+        let mode = if instance.detached {
+            "detached"
+        } else {
+            "foreground"
+        };
+        println!(
+            "{:<24} {:<16} {:>8} {:>8}  {:<10} {}",
+            instance.id,
+            instance.config,
+            instance.pid,
+            format_duration(instance.uptime_secs()),
+            mode,
+            gdb
+        );
+    }
+
+    Ok(())
+}
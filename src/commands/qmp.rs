@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::instance::{Instance, find_instance};
+use crate::utils::qmp::QmpClient;
+
+#[derive(Args)]
+#[clap(about = "Send a QMP command to a running QEMU instance")]
+pub struct QmpArgs {
+    #[arg(help = "Instance ID or configuration name")]
+    pub instance: String,
+
+    #[arg(help = "QMP command to execute (e.g., query-status)")]
+    pub command: String,
+
+    #[arg(help = "Command arguments as a JSON object (e.g., '{\"device\": \"ide1-cd0\"}')")]
+    pub arguments: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "events",
+        help = "Also print events received while the command ran"
+    )]
+    pub events: bool,
+}
+
+pub fn qmp_command(
+    instance: String,
+    command: String,
+    arguments: Option<String>,
+    events: bool,
+) -> Result<()> {
+    let arguments = arguments
+        .map(|json| serde_json::from_str::<Value>(&json))
+        .transpose()
+        .context("Command arguments must be valid JSON")?;
+    if arguments.as_ref().is_some_and(|args| !args.is_object()) {
+        anyhow::bail!("Command arguments must be a JSON object");
+    }
+
+    let instance = find_instance(&instance)?;
+    let mut client = connect_instance(&instance)?;
+    let reply = client.execute(&command, arguments)?;
+
+    if events {
+        for event in client.take_events() {
+            println!("{}", serde_json::to_string(&event)?);
+        }
+    }
+
+    match reply {
+        // e.g. human-monitor-command returns plain text
+        Value::String(text) => print!("{}", text),
+        other => println!("{}", serde_json::to_string_pretty(&other)?),
+    }
+
+    Ok(())
+}
+
+/// Open a QMP connection to a running instance
+pub(crate) fn connect_instance(instance: &Instance) -> Result<QmpClient> {
+    let socket = instance.sockets.get("qmp").with_context(|| {
+        format!(
+            "Instance '{}' was started without a QMP socket",
+            instance.id
+        )
+    })?;
+    QmpClient::connect_timeout(socket, Duration::from_secs(5))
+}
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use clap::Args;
 use std::fs;
 
-use crate::config::config_file;
+use crate::config::{config_children, config_env_file, config_files};
 
 #[derive(Args)]
 #[clap(about = "Remove a saved QEMU configuration")]
@@ -12,13 +12,29 @@ pub struct RemoveArgs {
 }
 
 pub fn remove_command(name: String) -> Result<()> {
-    let config_path = config_file(&name)?;
-    if !config_path.exists() {
+    let config_paths = config_files(&name)?;
+    if config_paths.is_empty() {
         anyhow::bail!("Configuration '{}' does not exist, cannot delete", name);
     }
 
-    fs::remove_file(&config_path).context("Failed to delete config file")?;
+    // A name saved in several formats goes away completely
+    for config_path in &config_paths {
+        fs::remove_file(config_path).context("Failed to delete config file")?;
+    }
+    let env_path = config_env_file(&name)?;
+    if env_path.exists() {
+        fs::remove_file(&env_path).context("Failed to delete env file")?;
+    }
     println!("Configuration '{}' deleted", name);
 
+    let children = config_children(&name)?;
+    if !children.is_empty() {
+        eprintln!(
+            "WARNING: {} still extend '{}' and can no longer be loaded",
+            children.join(", "),
+            name
+        );
+    }
+
     Ok(())
 }
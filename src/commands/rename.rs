@@ -2,7 +2,10 @@ use anyhow::{Context, Result};
 use clap::Args;
 use std::fs;
 
-use crate::config::{QemuConfig, config_file};
+use crate::config::{
+    ConfigFormat, config_children, config_env_file, config_file, config_file_as, config_files,
+    read_config, write_config,
+};
 use crate::utils::io::prompt_user_default_no;
 
 #[derive(Args)]
@@ -40,8 +43,11 @@ pub fn rename_command(
         anyhow::bail!("Configuration '{}' does not exist, cannot rename", old_name);
     }
 
-    let new_config_path = config_file(&new_name)?;
-    if new_config_path.exists() && !force {
+    // The renamed configuration keeps its file format
+    let format = ConfigFormat::from_path(&old_config_path).unwrap_or(ConfigFormat::Json);
+    let new_config_path = config_file_as(&new_name, format)?;
+    let overwritten = config_files(&new_name)?;
+    if !overwritten.is_empty() && !force {
         println!(
             "Configuration '{}' already exists, overwrite? [y/N]",
             new_name
@@ -53,22 +59,40 @@ pub fn rename_command(
     }
 
     // Read the old configuration
-    let config_json = fs::read_to_string(&old_config_path).context("Failed to read config file")?;
-    let mut config: QemuConfig =
-        serde_json::from_str(&config_json).context("Failed to deserialize configuration")?;
+    let mut config = read_config(&old_name, &old_config_path)?;
+
+    for path in &overwritten {
+        fs::remove_file(path).context("Failed to delete overwritten config file")?;
+    }
 
-    // Update description if provided
     if let Some(new_desc) = desc {
+        // Update description and save to new location
         config.desc = Some(new_desc);
+        write_config(&new_config_path, &config)?;
+        fs::remove_file(&old_config_path).context("Failed to delete old config file")?;
+    } else {
+        // Move the file as is, keeping any comments in TOML or YAML
+        fs::rename(&old_config_path, &new_config_path).context("Failed to rename config file")?;
     }
 
-    // Save to new location
-    let new_config_json =
-        serde_json::to_string_pretty(&config).context("Failed to serialize configuration")?;
-    fs::write(&new_config_path, new_config_json).context("Failed to save new config file")?;
+    // Move the env file stored next to the configuration along with it
+    let old_env_path = config_env_file(&old_name)?;
+    let new_env_path = config_env_file(&new_name)?;
+    if old_env_path.exists() {
+        fs::rename(&old_env_path, &new_env_path).context("Failed to rename env file")?;
+    } else if new_env_path.exists() {
+        // It belonged to the configuration that was overwritten
+        fs::remove_file(&new_env_path).context("Failed to delete env file")?;
+    }
 
-    // Remove old configuration
-    fs::remove_file(&old_config_path).context("Failed to delete old config file")?;
+    // Keep configurations that extend this one pointing at it
+    for child in config_children(&old_name)? {
+        let child_path = config_file(&child)?;
+        let mut child_config = read_config(&child, &child_path)?;
+        child_config.extends = Some(new_name.clone());
+        write_config(&child_path, &child_config)?;
+        println!("Updated '{}' to extend '{}'", child, new_name);
+    }
 
     if let Some(desc) = &config.desc {
         println!(
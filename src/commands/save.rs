@@ -1,10 +1,20 @@
 use anyhow::{Context, Result};
 use clap::Args;
 use std::fs;
+use std::path::PathBuf;
 
-use crate::config::{QemuConfig, config_file};
+use crate::config::{
+    ConfigFormat, GdbPort, ParamDecl, PortForward, QemuConfig, SCHEMA_VERSION, SidecarSpec,
+    SshSettings, check_defaults, config_file, config_file_as, config_files, load_config,
+    resolve_extends, validate_config, write_config,
+};
 use crate::utils::io::{prompt_user, prompt_user_default_no};
 use crate::utils::qemu::get_qemu_version;
+use crate::utils::qemu_options::{tokenize, untokenize};
+use crate::utils::shell::shell_split;
+
+/// Options that start a GDB server or pause the CPU, which `vex exec -d` adds itself
+const DEBUG_FLAGS: &[&str] = &["-s", "-S"];
 
 #[derive(Args)]
 #[clap(about = "Save QEMU configuration")]
@@ -29,6 +39,102 @@ pub struct SaveArgs {
     )]
     pub desc: Option<String>,
 
+    #[arg(
+        long = "extends",
+        value_name = "BASE",
+        help = "Build on a saved configuration; the arguments given are merged into its arguments"
+    )]
+    pub extends: Option<String>,
+
+    #[arg(
+        long = "remove-arg",
+        value_name = "ARGS",
+        allow_hyphen_values = true,
+        requires = "extends",
+        help = "Drop inherited arguments, e.g. '-nographic' or '-device virtio-rng-pci' (repeatable)"
+    )]
+    pub remove_args: Vec<String>,
+
+    #[arg(
+        long = "replace-arg",
+        value_name = "ARGS",
+        allow_hyphen_values = true,
+        requires = "extends",
+        help = "Replace every inherited occurrence of an option, e.g. '-drive file=b.img' (repeatable)"
+    )]
+    pub replace_args: Vec<String>,
+
+    #[arg(
+        long = "gdb-port",
+        value_name = "N|auto",
+        help = "Default GDB server port for 'vex exec -d', or 'auto' to pick a free one"
+    )]
+    pub gdb_port: Option<GdbPort>,
+
+    #[arg(
+        short = 'p',
+        long = "param",
+        value_name = "NAME[:TYPE][=DEFAULT]",
+        help = "Declare a parameter referenced as ${NAME}: NAME[:TYPE][=DEFAULT][#HELP] (types: string, path, size, int)"
+    )]
+    pub params: Vec<ParamDecl>,
+
+    #[arg(
+        long = "forward",
+        value_name = "[tcp|udp:]HOST:GUEST",
+        help = "Forward a host port to a guest port; HOST may be 'auto' (repeatable)"
+    )]
+    pub forwards: Vec<PortForward>,
+
+    #[arg(
+        long = "ssh-user",
+        value_name = "USER",
+        help = "Guest user for 'vex ssh' (also forwards guest port 22)"
+    )]
+    pub ssh_user: Option<String>,
+
+    #[arg(
+        long = "ssh-key",
+        value_name = "PATH",
+        help = "Private key for 'vex ssh' (also forwards guest port 22)"
+    )]
+    pub ssh_key: Option<String>,
+
+    #[arg(
+        long = "sidecar",
+        value_name = "NAME=COMMAND",
+        help = "Helper daemon started before QEMU; its command gets ${socket}, QEMU args ${sidecar.NAME} (repeatable)"
+    )]
+    pub sidecars: Vec<SidecarSpec>,
+
+    #[arg(
+        long = "pre-exec",
+        value_name = "COMMAND",
+        help = "Shell command to run before QEMU starts (repeatable)"
+    )]
+    pub pre_exec: Vec<String>,
+
+    #[arg(
+        long = "post-exec",
+        value_name = "COMMAND",
+        help = "Shell command to run after QEMU exits, even on failure (repeatable)"
+    )]
+    pub post_exec: Vec<String>,
+
+    #[arg(
+        long = "cwd",
+        value_name = "DIR",
+        help = "Working directory to run QEMU from (default: the current directory)"
+    )]
+    pub cwd: Option<PathBuf>,
+
+    #[arg(
+        long = "format",
+        value_name = "json|toml|yaml",
+        help = "File format to save in (default: the existing file's format, else json)"
+    )]
+    pub format: Option<ConfigFormat>,
+
     #[arg(
         short = 'f',
         long = "force",
@@ -37,17 +143,37 @@ pub struct SaveArgs {
     pub force: bool,
 }
 
-pub fn save_command(
-    force: bool,
-    name: String,
-    desc: Option<String>,
-    qemu_bin: String,
-    qemu_args: Vec<String>,
-) -> Result<()> {
-    let config_path = config_file(&name)?;
+pub fn save_command(args: SaveArgs) -> Result<()> {
+    let SaveArgs {
+        name,
+        qemu_bin,
+        qemu_args,
+        desc,
+        extends,
+        remove_args,
+        replace_args,
+        gdb_port,
+        params,
+        forwards,
+        ssh_user,
+        ssh_key,
+        sidecars,
+        pre_exec,
+        post_exec,
+        cwd,
+        format,
+        force,
+    } = args;
+
+    let existing = config_files(&name)?;
+    let config_path = match format {
+        Some(format) => config_file_as(&name, format)?,
+        None => config_file(&name)?,
+    };
 
     // Check if debug parameters -s or -S are present
-    let has_debug_args = qemu_args.iter().any(|arg| arg == "-s" || arg == "-S");
+    let tokens = tokenize(&qemu_args);
+    let has_debug_args = tokens.iter().any(|token| token.is(DEBUG_FLAGS));
 
     let mut final_args = qemu_args.clone();
 
@@ -63,11 +189,12 @@ pub fn save_command(
 
         if prompt_user()? {
             // User chose to skip debug parameters
-            final_args = qemu_args
+            let kept: Vec<_> = tokens
                 .iter()
-                .filter(|&arg| arg != "-s" && arg != "-S")
-                .cloned()
+                .filter(|token| !token.is(DEBUG_FLAGS))
+                .copied()
                 .collect();
+            final_args = untokenize(&kept);
             println!(
                 "Debug parameters have been skipped, saved configuration will not include -s or -S parameters"
             );
@@ -83,14 +210,46 @@ pub fn save_command(
     if let Some(v) = &qemu_version {
         println!("Detected QEMU version: {}", v);
     }
-    let config = QemuConfig {
+    let mut config = QemuConfig {
+        schema_version: SCHEMA_VERSION,
         qemu_bin: qemu_bin.clone(),
         args: final_args,
         desc,
         qemu_version,
+        extends,
+        remove_args: split_arg_lists(&remove_args)?,
+        replace_args: split_arg_lists(&replace_args)?,
+        gdb_port,
+        params: params
+            .into_iter()
+            .map(|decl| (decl.name, decl.spec))
+            .collect(),
+        forwards,
+        ssh: (ssh_user.is_some() || ssh_key.is_some()).then_some(SshSettings {
+            user: ssh_user,
+            key: ssh_key,
+        }),
+        cwd: Some(save_cwd(cwd)?),
+        sidecars,
+        pre_exec,
+        post_exec,
     };
+    if config.extends.is_some() {
+        // Check the parent chain now rather than on the first exec
+        let inherited = resolve_extends(&name, config.clone())?;
+        validate_config(&inherited.config)?;
+        if let Some(parent) = &config.extends
+            && load_config(parent)?.qemu_bin == config.qemu_bin
+        {
+            // Keep following the parent's binary if it changes later
+            config.qemu_bin.clear();
+        }
+    } else {
+        validate_config(&config)?;
+    }
+    check_defaults(&config)?;
 
-    if config_path.exists() && !force {
+    if !existing.is_empty() && !force {
         println!("Configuration '{}' already exists, overwrite? [y/N]", name);
         if !prompt_user_default_no()? {
             println!("Save cancelled");
@@ -98,9 +257,11 @@ pub fn save_command(
         }
     }
 
-    let config_json =
-        serde_json::to_string_pretty(&config).context("Failed to serialize configuration")?;
-    fs::write(&config_path, config_json).context("Failed to save config file")?;
+    write_config(&config_path, &config)?;
+    // Saving in another format replaces the old file instead of duplicating the name
+    for old_path in existing.iter().filter(|path| **path != config_path) {
+        fs::remove_file(old_path).context("Failed to delete old config file")?;
+    }
 
     if let Some(desc) = &config.desc {
         println!(
@@ -113,3 +274,24 @@ pub fn save_command(
 
     Ok(())
 }
+
+/// Split `--remove-arg`/`--replace-arg` values such as `'-device virtio-rng-pci'` into argv
+fn split_arg_lists(lists: &[String]) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    for list in lists {
+        let split = shell_split(list)
+            .map_err(|err| anyhow::anyhow!("Invalid arguments '{}': {}", list, err))?;
+        args.extend(split);
+    }
+    Ok(args)
+}
+
+/// Working directory to record, so relative paths in the arguments keep working
+fn save_cwd(cwd: Option<PathBuf>) -> Result<PathBuf> {
+    let dir = match cwd {
+        Some(dir) => dir,
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    fs::canonicalize(&dir)
+        .with_context(|| format!("Working directory '{}' does not exist", dir.display()))
+}
@@ -0,0 +1,311 @@
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::commands::exec::{substitute_params, substitute_value};
+use crate::commands::qmp::connect_instance;
+use crate::config::{load_config, load_env_files, merge_variables};
+use crate::instance::find_running_instance;
+use crate::instance::types::unix_now;
+use crate::utils::qmp::QmpClient;
+use crate::utils::snapshot::{
+    SnapshotInfo, image_snapshot, list_image_snapshots, qcow2_drives, qemu_img_bin,
+};
+use crate::utils::time::format_duration;
+
+#[derive(Args)]
+#[clap(about = "Manage snapshots of the qcow2 drives used by a configuration")]
+pub struct SnapshotArgs {
+    #[command(subcommand)]
+    pub action: SnapshotAction,
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    #[clap(about = "Create a snapshot (includes VM state when the instance is running)")]
+    Create {
+        #[arg(help = "Configuration name or instance ID")]
+        name: String,
+        #[arg(help = "Snapshot tag")]
+        tag: String,
+    },
+    #[clap(about = "List snapshots")]
+    List {
+        #[arg(help = "Configuration name or instance ID")]
+        name: String,
+    },
+    #[clap(about = "Restore a snapshot")]
+    Restore {
+        #[arg(help = "Configuration name or instance ID")]
+        name: String,
+        #[arg(help = "Snapshot tag")]
+        tag: String,
+    },
+    #[clap(about = "Delete a snapshot")]
+    Delete {
+        #[arg(help = "Configuration name or instance ID")]
+        name: String,
+        #[arg(help = "Snapshot tag")]
+        tag: String,
+    },
+}
+
+pub fn snapshot_command(action: SnapshotAction) -> Result<()> {
+    match action {
+        SnapshotAction::Create { name, tag } => create_snapshot(&name, &tag),
+        SnapshotAction::List { name } => list_snapshots(&name),
+        SnapshotAction::Restore { name, tag } => restore_snapshot(&name, &tag),
+        SnapshotAction::Delete { name, tag } => delete_snapshot(&name, &tag),
+    }
+}
+
+fn create_snapshot(name: &str, tag: &str) -> Result<()> {
+    match find_running_instance(name)? {
+        Some(instance) => {
+            let mut client = connect_instance(&instance)?;
+            let nodes = qmp_snapshot_nodes(&mut client)?;
+            run_snapshot_job(&mut client, "snapshot-save", tag, &nodes, true)?;
+            println!(
+                "Snapshot '{}' of running instance '{}' created (disk and VM state)",
+                tag, instance.id
+            );
+        }
+        None => {
+            let (qemu_img, drives) = offline_drives(name)?;
+            for drive in &drives {
+                image_snapshot(&qemu_img, "-c", tag, drive)?;
+            }
+            println!(
+                "Snapshot '{}' of configuration '{}' created on {} drive(s) (disk only)",
+                tag,
+                name,
+                drives.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn list_snapshots(name: &str) -> Result<()> {
+    // Tag -> (snapshot info, drives holding it)
+    let mut by_tag: BTreeMap<String, (SnapshotInfo, Vec<String>)> = BTreeMap::new();
+
+    match find_running_instance(name)? {
+        Some(instance) => {
+            let mut client = connect_instance(&instance)?;
+            for (node, snapshots) in qmp_block_snapshots(&mut client)? {
+                for snapshot in snapshots {
+                    by_tag
+                        .entry(snapshot.name.clone())
+                        .or_insert_with(|| (snapshot, Vec::new()))
+                        .1
+                        .push(node.clone());
+                }
+            }
+        }
+        None => {
+            let (qemu_img, drives) = offline_drives(name)?;
+            for drive in &drives {
+                for snapshot in list_image_snapshots(&qemu_img, drive)? {
+                    by_tag
+                        .entry(snapshot.name.clone())
+                        .or_insert_with(|| (snapshot, Vec::new()))
+                        .1
+                        .push(drive.display().to_string());
+                }
+            }
+        }
+    }
+
+    if by_tag.is_empty() {
+        println!("No snapshots found for '{}'.", name);
+        return Ok(());
+    }
+
+    println!("Snapshots of '{}':", name);
+    println!("  {:<20} {:>10} {:>12}  DRIVES", "TAG", "VM STATE", "AGE");
+    for (tag, (info, drives)) in by_tag {
+        let vm_state = if info.vm_state_size > 0 {
+            format!("{} MiB", info.vm_state_size.div_ceil(1024 * 1024))
+        } else {
+            "-".to_string()
+        };
+        let age = format!(
+            "{} ago",
+            format_duration(unix_now().saturating_sub(info.date_sec))
+        );
+        println!(
+            "  {:<20} {:>10} {:>12}  {}",
+            tag,
+            vm_state,
+            age,
+            drives.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn restore_snapshot(name: &str, tag: &str) -> Result<()> {
+    match find_running_instance(name)? {
+        Some(instance) => {
+            let mut client = connect_instance(&instance)?;
+            let nodes = qmp_snapshot_nodes(&mut client)?;
+            run_snapshot_job(&mut client, "snapshot-load", tag, &nodes, true)?;
+            println!(
+                "Running instance '{}' restored to snapshot '{}'",
+                instance.id, tag
+            );
+        }
+        None => {
+            let (qemu_img, drives) = offline_drives(name)?;
+            // Check every drive first so a missing tag doesn't leave drives out of sync
+            for drive in &drives {
+                let snapshots = list_image_snapshots(&qemu_img, drive)?;
+                if !snapshots.iter().any(|s| s.name == tag) {
+                    anyhow::bail!("Snapshot '{}' not found on drive {}", tag, drive.display());
+                }
+            }
+            for drive in &drives {
+                image_snapshot(&qemu_img, "-a", tag, drive)?;
+            }
+            println!(
+                "Configuration '{}' restored to snapshot '{}' on {} drive(s)",
+                name,
+                tag,
+                drives.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn delete_snapshot(name: &str, tag: &str) -> Result<()> {
+    match find_running_instance(name)? {
+        Some(instance) => {
+            let mut client = connect_instance(&instance)?;
+            let nodes = qmp_snapshot_nodes(&mut client)?;
+            run_snapshot_job(&mut client, "snapshot-delete", tag, &nodes, false)?;
+            println!(
+                "Snapshot '{}' deleted from running instance '{}'",
+                tag, instance.id
+            );
+        }
+        None => {
+            let (qemu_img, drives) = offline_drives(name)?;
+            for drive in &drives {
+                image_snapshot(&qemu_img, "-d", tag, drive)?;
+            }
+            println!("Snapshot '{}' deleted from configuration '{}'", tag, name);
+        }
+    }
+    Ok(())
+}
+
+/// Resolve the qemu-img binary and qcow2 drives of a stopped configuration.
+///
+/// Arguments are resolved the way `vex exec` would, and relative paths are
+/// taken from the configuration's working directory.
+fn offline_drives(name: &str) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let config = load_config(name)?;
+    // Parameters can't be set here, so only their defaults apply
+    let defaults = config
+        .params
+        .iter()
+        .filter_map(|(name, spec)| Some((name.clone(), spec.default.clone()?)))
+        .collect();
+    let vars = merge_variables(&defaults, &load_env_files(name, &[])?);
+    let args = substitute_params(&config.args, &vars)
+        .with_context(|| format!("Failed to resolve arguments of configuration '{}'", name))?;
+    let in_cwd = |path: PathBuf| match &config.cwd {
+        Some(dir) => dir.join(path),
+        None => path,
+    };
+    let drives: Vec<PathBuf> = qcow2_drives(&args).into_iter().map(in_cwd).collect();
+    if drives.is_empty() {
+        anyhow::bail!(
+            "Configuration '{}' has no qcow2 drives, snapshots are not supported",
+            name
+        );
+    }
+    let qemu_bin = substitute_value(&config.qemu_bin, &vars)?;
+    // A bare QEMU name is found in PATH, a relative path from the working directory
+    let qemu_bin = if qemu_bin.contains('/') {
+        in_cwd(PathBuf::from(qemu_bin)).display().to_string()
+    } else {
+        qemu_bin
+    };
+    Ok((qemu_img_bin(&qemu_bin), drives))
+}
+
+/// List writable qcow2 block nodes and their snapshots via `query-block`
+fn qmp_block_snapshots(client: &mut QmpClient) -> Result<Vec<(String, Vec<SnapshotInfo>)>> {
+    let blocks = client.execute("query-block", None)?;
+    let mut nodes = Vec::new();
+    for block in blocks.as_array().into_iter().flatten() {
+        let Some(inserted) = block.get("inserted") else {
+            continue;
+        };
+        if inserted["drv"] != "qcow2" || inserted["ro"] == true {
+            continue;
+        }
+        let Some(node) = inserted["node-name"].as_str() else {
+            continue;
+        };
+        let snapshots = inserted["image"]["snapshots"]
+            .as_array()
+            .map(|list| list.iter().filter_map(SnapshotInfo::from_json).collect())
+            .unwrap_or_default();
+        nodes.push((node.to_string(), snapshots));
+    }
+    Ok(nodes)
+}
+
+fn qmp_snapshot_nodes(client: &mut QmpClient) -> Result<Vec<String>> {
+    let nodes: Vec<String> = qmp_block_snapshots(client)?
+        .into_iter()
+        .map(|(node, _)| node)
+        .collect();
+    if nodes.is_empty() {
+        anyhow::bail!("Running instance has no writable qcow2 drives, snapshots are not supported");
+    }
+    Ok(nodes)
+}
+
+/// Start a snapshot-save/load/delete job and wait for it to finish
+fn run_snapshot_job(
+    client: &mut QmpClient,
+    command: &str,
+    tag: &str,
+    nodes: &[String],
+    with_vmstate: bool,
+) -> Result<()> {
+    let job_id = format!("vex-{}-{}", command, std::process::id());
+    let mut arguments = json!({ "job-id": job_id, "tag": tag, "devices": nodes });
+    if with_vmstate {
+        // The VM state is stored alongside the first drive
+        arguments["vmstate"] = json!(nodes[0]);
+    }
+    client.execute(command, Some(arguments))?;
+
+    loop {
+        let jobs = client.execute("query-jobs", None)?;
+        let job = jobs
+            .as_array()
+            .and_then(|jobs| jobs.iter().find(|job| job["id"] == job_id))
+            .cloned()
+            .with_context(|| format!("Snapshot job '{}' disappeared", job_id))?;
+
+        if job["status"] == "concluded" {
+            client.execute("job-dismiss", Some(json!({ "id": job_id })))?;
+            if let Some(error) = job.get("error").and_then(Value::as_str) {
+                anyhow::bail!("{} failed: {}", command, error);
+            }
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
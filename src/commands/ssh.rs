@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::config::load_config;
+use crate::instance::find_instance;
+use crate::utils::net::wait_for_ssh;
+use crate::utils::qemu::find_host_forward;
+use crate::utils::time::{format_duration, parse_duration};
+
+/// Guest port of the SSH server
+pub const SSH_GUEST_PORT: u16 = 22;
+
+/// Options that stop ssh from tracking host keys of throwaway VMs
+const THROWAWAY_HOST_OPTIONS: &[&str] = &[
+    "-o",
+    "StrictHostKeyChecking=no",
+    "-o",
+    "UserKnownHostsFile=/dev/null",
+    "-o",
+    "LogLevel=ERROR",
+];
+
+#[derive(Args)]
+#[clap(about = "Open an SSH session to a running QEMU instance")]
+pub struct SshArgs {
+    #[arg(help = "Instance ID or configuration name")]
+    pub instance: String,
+
+    #[arg(
+        short = 'l',
+        long = "user",
+        help = "Guest user, overriding the configuration's setting"
+    )]
+    pub user: Option<String>,
+
+    #[arg(
+        short = 'i',
+        long = "key",
+        value_name = "PATH",
+        help = "Private key, overriding the configuration's setting"
+    )]
+    pub key: Option<String>,
+
+    #[arg(
+        short = 'w',
+        long = "wait",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Retry until the guest's sshd answers, up to this long (e.g. 60s)"
+    )]
+    pub wait: Option<Duration>,
+
+    #[arg(last = true, help = "Command to run in the guest after '--'")]
+    pub command: Vec<String>,
+}
+
+pub fn ssh_command(args: SshArgs) -> Result<()> {
+    let instance = find_instance(&args.instance)?;
+
+    let (addr, port) = find_host_forward(&instance.args, SSH_GUEST_PORT).with_context(|| {
+        format!(
+            "Instance '{}' has no host port forwarded to guest port {}. Add one with 'vex save --forward auto:{}' or '--ssh-user'",
+            instance.id, SSH_GUEST_PORT, SSH_GUEST_PORT
+        )
+    })?;
+
+    // The configuration may have been removed since the instance started
+    let settings = load_config(&instance.config)
+        .ok()
+        .and_then(|config| config.ssh)
+        .unwrap_or_default();
+    let user = args.user.or(settings.user);
+    let key = args.key.or(settings.key);
+
+    if let Some(timeout) = args.wait {
+        println!("Waiting for sshd on {}:{}...", addr, port);
+        if !wait_for_ssh(&addr, port, timeout) {
+            anyhow::bail!(
+                "sshd of instance '{}' did not answer within {}",
+                instance.id,
+                format_duration(timeout.as_secs())
+            );
+        }
+    }
+
+    let mut ssh = Command::new("ssh");
+    ssh.arg("-p")
+        .arg(port.to_string())
+        .args(THROWAWAY_HOST_OPTIONS);
+    if let Some(key) = key {
+        ssh.arg("-i").arg(expand_home(&key));
+    }
+    let target = match user {
+        Some(user) => format!("{}@{}", user, addr),
+        None => addr,
+    };
+    ssh.arg(target);
+    if !args.command.is_empty() {
+        ssh.arg("--").args(&args.command);
+    }
+
+    // Hand the terminal over to ssh; its exit status becomes ours
+    let err = ssh.exec();
+    Err(err).context("Failed to run ssh")
+}
+
+/// Expand a leading `~/` to the user's home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).display().to_string(),
+        _ => path.to_string(),
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use clap::Args;
+use std::time::Duration;
+
+use crate::instance::{
+    find_instance, find_running_instance, finish_instance, prune_stale_instances,
+};
+use crate::utils::process::wait_for_exit;
+
+#[derive(Args)]
+#[clap(about = "Stop a running QEMU instance gracefully")]
+pub struct StopArgs {
+    #[arg(help = "Instance ID or configuration name")]
+    pub instance: String,
+
+    #[arg(
+        short = 't',
+        long = "timeout",
+        default_value_t = 10,
+        help = "Seconds to wait before killing the instance"
+    )]
+    pub timeout: u64,
+}
+
+pub fn stop_command(query: String, timeout: u64) -> Result<()> {
+    let exited = finish_exited_instances(&query)?;
+    let instance = match find_running_instance(&query)? {
+        Some(instance) => instance,
+        None if exited => return Ok(()),
+        None => find_instance(&query)?,
+    };
+
+    instance.signal(libc::SIGTERM)?;
+    if wait_for_exit(instance.pid, Duration::from_secs(timeout)) {
+        println!("Instance '{}' stopped", instance.id);
+    } else {
+        println!(
+            "Instance '{}' did not stop within {}s, killing it",
+            instance.id, timeout
+        );
+        instance.signal(libc::SIGKILL)?;
+        wait_for_exit(instance.pid, Duration::from_secs(5));
+        println!("Instance '{}' killed", instance.id);
+    }
+
+    finish_instance(&instance)?;
+    Ok(())
+}
+
+/// Finish instances matching `query` that exited on their own, e.g. on guest
+/// poweroff, returning whether there were any
+pub(crate) fn finish_exited_instances(query: &str) -> Result<bool> {
+    let exited = prune_stale_instances(Some(query))?;
+    for instance in &exited {
+        println!("Instance '{}' had already exited", instance.id);
+    }
+    Ok(!exited.is_empty())
+}
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::{Path, PathBuf};
+
+use crate::commands::exec::{ExecArgs, exec_command};
+use crate::compose::{DEFAULT_COMPOSE_FILE, instance_name, load_compose};
+use crate::config::{config_env_file, load_config, resolve_extends, validate_config};
+use crate::instance::find_running_instance;
+
+#[derive(Args)]
+#[clap(about = "Start all VMs of a compose file in dependency order")]
+pub struct UpArgs {
+    #[arg(
+        short = 'f',
+        long = "file",
+        default_value = DEFAULT_COMPOSE_FILE,
+        help = "Compose file to use"
+    )]
+    pub file: PathBuf,
+
+    #[arg(
+        long = "dry-run",
+        help = "Print the QEMU command of each VM instead of starting it"
+    )]
+    pub dry_run: bool,
+}
+
+pub fn up_command(file: PathBuf, dry_run: bool) -> Result<()> {
+    let compose = load_compose(&file)?;
+    let project = compose.project_name(&file);
+    let order = compose.start_order()?;
+    // Inline VMs run from the compose file's directory, like saved ones from theirs
+    let compose_dir = file
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve compose file {}", file.display()))?
+        .parent()
+        .map(Path::to_path_buf);
+
+    // Report port collisions before any VM starts
+    compose.check_network_ports(&project, |vm| {
+        Ok(find_running_instance(&instance_name(&project, vm))?.is_some())
+    })?;
+
+    for vm in &order {
+        let name = instance_name(&project, vm);
+        if !dry_run && find_running_instance(&name)?.is_some() {
+            println!("VM '{}' is already running", vm.name);
+            continue;
+        }
+
+        let config = match (&vm.config, &vm.inline) {
+            (Some(saved), _) => load_config(saved)?,
+            (None, Some(inline)) => {
+                let mut inline = inline.clone();
+                if inline.cwd.is_none() {
+                    inline.cwd = compose_dir.clone();
+                }
+                resolve_extends(&name, inline)?.config
+            }
+            (None, None) => unreachable!("validated by load_compose"),
+        };
+        validate_config(&config)
+            .with_context(|| format!("Invalid configuration for VM '{}'", vm.name))?;
+
+        // The instance is named after the VM, but a saved configuration keeps its own env file
+        let env_files = match &vm.config {
+            Some(saved) => Some(config_env_file(saved)?).filter(|file| file.exists()),
+            None => None,
+        };
+
+        let mut extra_args = vm.args.clone();
+        extra_args.extend(compose.network_args(&project, vm, &order));
+
+        if dry_run {
+            println!("# {}", vm.name);
+        }
+        exec_command(ExecArgs {
+            name: Some(name),
+            debug: false,
+            gdb_port: None,
+            full: false,
+            detach: !dry_run,
+            from_snapshot: None,
+            dry_run,
+            json: false,
+            extra_args,
+            sets: vm.set.clone().into_iter().collect(),
+            timeout: None,
+            expect: Vec::new(),
+            fail_on: Vec::new(),
+            rerun: None,
+            env_files: env_files.into_iter().collect(),
+            cwd: None,
+            config: Some(Box::new(config)),
+        })
+        .with_context(|| {
+            format!(
+                "Failed to start VM '{}'. Use 'vex down' to stop the VMs already started",
+                vm.name
+            )
+        })?;
+    }
+
+    Ok(())
+}
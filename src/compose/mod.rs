@@ -0,0 +1,8 @@
+pub mod plan;
+pub mod types;
+
+pub use plan::{instance_name, load_compose};
+pub use types::{ComposeFile, ComposeVm, NetworkKind, NetworkSpec};
+
+/// Compose file used when none is given
+pub const DEFAULT_COMPOSE_FILE: &str = "vex-compose.json";
@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::compose::{ComposeFile, ComposeVm, NetworkKind};
+use crate::config::Protocol;
+use crate::utils::net::is_addr_port_free;
+
+/// Default NIC model for compose networks
+const DEFAULT_NIC_MODEL: &str = "virtio-net-pci";
+
+/// Load and validate a compose file
+pub fn load_compose(path: &Path) -> Result<ComposeFile> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read compose file {}", path.display()))?;
+    let compose: ComposeFile = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse compose file {}", path.display()))?;
+    compose.validate()?;
+    Ok(compose)
+}
+
+impl ComposeFile {
+    /// Project name, falling back to the directory holding the compose file
+    pub fn project_name(&self, path: &Path) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        fs::canonicalize(path)
+            .ok()
+            .and_then(|path| Some(path.parent()?.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "vex".to_string())
+    }
+
+    /// Check names, references and network membership
+    fn validate(&self) -> Result<()> {
+        let mut names = BTreeSet::new();
+        for vm in &self.vms {
+            if !names.insert(vm.name.as_str()) {
+                anyhow::bail!("VM '{}' is defined more than once", vm.name);
+            }
+            if vm.config.is_some() == vm.inline.is_some() {
+                anyhow::bail!(
+                    "VM '{}' needs exactly one of 'config' (a saved configuration) or 'inline'",
+                    vm.name
+                );
+            }
+        }
+
+        for vm in &self.vms {
+            for dep in &vm.depends_on {
+                if !names.contains(dep.as_str()) {
+                    anyhow::bail!("VM '{}' depends on unknown VM '{}'", vm.name, dep);
+                }
+            }
+            for network in &vm.networks {
+                if !self.networks.contains_key(network) {
+                    anyhow::bail!("VM '{}' uses unknown network '{}'", vm.name, network);
+                }
+            }
+        }
+
+        for (name, spec) in &self.networks {
+            let members = self.vms.iter().filter(|vm| vm.networks.contains(name));
+            if spec.kind == NetworkKind::Socket && members.count() != 2 {
+                anyhow::bail!("Socket network '{}' must connect exactly two VMs", name);
+            }
+        }
+
+        self.start_order().map(|_| ())
+    }
+
+    /// VMs ordered so that each starts after its dependencies, keeping file order otherwise
+    pub fn start_order(&self) -> Result<Vec<&ComposeVm>> {
+        let mut order: Vec<&ComposeVm> = Vec::with_capacity(self.vms.len());
+        let mut started = BTreeSet::new();
+        while order.len() < self.vms.len() {
+            let next = self.vms.iter().find(|vm| {
+                !started.contains(vm.name.as_str())
+                    && vm
+                        .depends_on
+                        .iter()
+                        .all(|dep| started.contains(dep.as_str()))
+            });
+            match next {
+                Some(vm) => {
+                    started.insert(vm.name.as_str());
+                    order.push(vm);
+                }
+                None => {
+                    let blocked: Vec<&str> = self
+                        .vms
+                        .iter()
+                        .filter(|vm| !started.contains(vm.name.as_str()))
+                        .map(|vm| vm.name.as_str())
+                        .collect();
+                    anyhow::bail!("Dependency cycle between VMs: {}", blocked.join(", "));
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// Multicast group or listen address of a network, derived from the names unless set
+    pub fn network_addr(&self, project: &str, network: &str) -> String {
+        let spec = &self.networks[network];
+        let seed = format!("{}/{}", project, network);
+        spec.addr.clone().unwrap_or_else(|| match spec.kind {
+            NetworkKind::Mcast => format!("230.0.0.1:{}", derived_port(&seed)),
+            NetworkKind::Socket => format!("127.0.0.1:{}", derived_port(&seed)),
+        })
+    }
+
+    /// Check that the networks' ports don't collide with each other or with ports in use,
+    /// e.g. by another project whose derived port is the same.
+    ///
+    /// Networks with a member VM for which `running` holds are skipped, since that VM
+    /// holds the port itself.
+    pub fn check_network_ports(
+        &self,
+        project: &str,
+        running: impl Fn(&ComposeVm) -> Result<bool>,
+    ) -> Result<()> {
+        let mut used: Vec<(Protocol, String, &str)> = Vec::new();
+        for (network, spec) in &self.networks {
+            let addr = self.network_addr(project, network);
+            // Multicast members share a UDP port; a socket network listens on TCP
+            let proto = match spec.kind {
+                NetworkKind::Mcast => Protocol::Udp,
+                NetworkKind::Socket => Protocol::Tcp,
+            };
+            if let Some((_, _, other)) = used.iter().find(|(p, a, _)| *p == proto && *a == addr) {
+                anyhow::bail!(
+                    "Networks '{}' and '{}' both use {}; set 'addr' on one of them",
+                    other,
+                    network,
+                    addr
+                );
+            }
+            used.push((proto, addr.clone(), network));
+
+            let Some((host, port)) = addr
+                .rsplit_once(':')
+                .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+            else {
+                // Left for QEMU to report
+                continue;
+            };
+            let mut member_running = false;
+            for vm in self.vms.iter().filter(|vm| vm.networks.contains(network)) {
+                if running(vm)? {
+                    member_running = true;
+                    break;
+                }
+            }
+            if member_running {
+                continue;
+            }
+            // QEMU binds multicast networks to the port on all interfaces
+            let host = match spec.kind {
+                NetworkKind::Mcast => "",
+                NetworkKind::Socket => host,
+            };
+            if !is_addr_port_free(proto, host, port) {
+                anyhow::bail!(
+                    "Port {} of network '{}' ({}) is already in use, e.g. by another compose project; set 'addr' on the network to use another",
+                    port,
+                    network,
+                    addr
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// QEMU arguments attaching a VM to its networks.
+    ///
+    /// `order` is the start order, which decides the listening side of socket networks.
+    pub fn network_args(&self, project: &str, vm: &ComposeVm, order: &[&ComposeVm]) -> Vec<String> {
+        let mut args = Vec::new();
+        for network in &vm.networks {
+            let spec = &self.networks[network];
+            let id = format!("vexnet-{}", network);
+            let seed = format!("{}/{}", project, network);
+            let addr = self.network_addr(project, network);
+            let transport = match spec.kind {
+                NetworkKind::Mcast => format!("mcast={}", addr),
+                NetworkKind::Socket => {
+                    let listener = order
+                        .iter()
+                        .find(|member| member.networks.contains(network));
+                    if listener.is_some_and(|listener| listener.name == vm.name) {
+                        format!("listen={}", addr)
+                    } else {
+                        format!("connect={}", addr)
+                    }
+                }
+            };
+            args.push("-netdev".to_string());
+            args.push(format!("socket,id={},{}", id, transport));
+            args.push("-device".to_string());
+            args.push(format!(
+                "{},netdev={},mac={}",
+                spec.model.as_deref().unwrap_or(DEFAULT_NIC_MODEL),
+                id,
+                derived_mac(&format!("{}/{}", seed, vm.name))
+            ));
+        }
+        args
+    }
+}
+
+/// Instance name of a compose VM, used as configuration name of its runs
+pub fn instance_name(project: &str, vm: &ComposeVm) -> String {
+    format!("{}-{}", project, vm.name)
+}
+
+/// Stable port in 20000-39999 derived from a name
+fn derived_port(seed: &str) -> u16 {
+    20000 + (fnv1a(seed) % 20000) as u16
+}
+
+/// Stable locally administered MAC address derived from a name
+fn derived_mac(seed: &str) -> String {
+    let hash = fnv1a(seed).to_be_bytes();
+    format!("52:54:00:{:02x}:{:02x}:{:02x}", hash[5], hash[6], hash[7])
+}
+
+/// FNV-1a hash, stable across builds unlike `DefaultHasher`
+fn fnv1a(data: &str) -> u64 {
+    data.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::config::QemuConfig;
+
+/// Compose file describing several VMs and the networks between them
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComposeFile {
+    /// Project name prefixing instance names (default: directory of the compose file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Virtual networks, keyed by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub networks: BTreeMap<String, NetworkSpec>,
+    /// VMs in declaration order
+    pub vms: Vec<ComposeVm>,
+}
+
+/// Virtual network connecting VMs through QEMU socket netdevs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkSpec {
+    /// Transport used for the network
+    #[serde(default)]
+    pub kind: NetworkKind,
+    /// Multicast group or listen address as `ADDR:PORT` (derived from the names by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub addr: Option<String>,
+    /// NIC model of the VMs' network devices (default virtio-net-pci)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Transport of a virtual network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkKind {
+    /// UDP multicast group, any number of VMs
+    #[default]
+    Mcast,
+    /// TCP connection between exactly two VMs; the first one started listens
+    Socket,
+}
+
+/// One VM of a compose file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComposeVm {
+    /// VM name, unique within the compose file
+    pub name: String,
+    /// Saved configuration to run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<String>,
+    /// Configuration given in place, instead of a saved one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline: Option<QemuConfig>,
+    /// VMs that must be started before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Networks the VM is attached to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<String>,
+    /// Extra QEMU arguments, merged like those of `vex exec -- ...`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Parameter values, like `vex exec --set`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, String>,
+}
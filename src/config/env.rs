@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::storage::config_env_file;
+
+/// Variable loaded from an env file
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvFileVar {
+    pub value: String,
+    /// File the value was read from
+    pub source: PathBuf,
+}
+
+/// Parse an env file of `KEY=VALUE` lines.
+///
+/// Blank lines, `#` comments and a leading `export` are allowed. As in common
+/// dotenv files, a `#` after whitespace starts a comment in unquoted values, and
+/// values may be wrapped in single quotes (taken literally) or double quotes
+/// (with `\"` and `\\` escapes), followed only by an optional comment.
+pub fn parse_env_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read env file {}", path.display()))?;
+
+    let mut vars = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            anyhow::bail!(
+                "{}:{}: expected KEY=VALUE, found '{}'",
+                path.display(),
+                number + 1,
+                line
+            );
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!(
+                "{}:{}: invalid variable name '{}'",
+                path.display(),
+                number + 1,
+                key
+            );
+        }
+        let value = parse_env_value(value.trim())
+            .map_err(|err| anyhow::anyhow!("{}:{}: {}", path.display(), number + 1, err))?;
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+/// Unquote an env file value and drop a trailing comment
+fn parse_env_value(value: &str) -> Result<String, String> {
+    let mut chars = value.char_indices();
+    let quote = match chars.next() {
+        Some((_, quote @ ('"' | '\''))) => quote,
+        _ => {
+            // Unquoted: `#` only starts a comment after whitespace, so `a#b` stays intact
+            let end = value
+                .char_indices()
+                .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+                .map_or(value.len(), |(i, _)| i);
+            return Ok(value[..end].trim_end().to_string());
+        }
+    };
+
+    let mut unquoted = String::new();
+    let mut rest = None;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c == quote => {
+                rest = Some(&value[i + 1..]);
+                break;
+            }
+            '\\' if quote == '"' => match chars.next() {
+                Some((_, escaped @ ('"' | '\\'))) => unquoted.push(escaped),
+                Some((_, other)) => {
+                    unquoted.push('\\');
+                    unquoted.push(other);
+                }
+                None => unquoted.push('\\'),
+            },
+            c => unquoted.push(c),
+        }
+    }
+    let Some(rest) = rest else {
+        return Err(format!("missing closing {} in {}", quote, value));
+    };
+    let rest = rest.trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected text after closing quote in {}", value));
+    }
+    Ok(unquoted)
+}
+
+/// Load the configuration's own env file (`<name>.env`, if any) and then `extra` files,
+/// later files overriding earlier ones
+pub fn load_env_files(name: &str, extra: &[PathBuf]) -> Result<BTreeMap<String, EnvFileVar>> {
+    let own = config_env_file(name)?;
+    let files = own
+        .exists()
+        .then_some(own)
+        .into_iter()
+        .chain(extra.iter().cloned());
+
+    let mut vars = BTreeMap::new();
+    for file in files {
+        for (key, value) in parse_env_file(&file)? {
+            vars.insert(
+                key,
+                EnvFileVar {
+                    value,
+                    source: file.clone(),
+                },
+            );
+        }
+    }
+    Ok(vars)
+}
+
+/// Combine parameters and env file variables into one substitution map.
+///
+/// Parameters win over everything; env file values are only used for variables
+/// not set in the process environment, so explicit variables win over the files.
+pub fn merge_variables(
+    params: &BTreeMap<String, String>,
+    file_vars: &BTreeMap<String, EnvFileVar>,
+) -> BTreeMap<String, String> {
+    let mut vars: BTreeMap<String, String> = file_vars
+        .iter()
+        .filter(|(key, _)| std::env::var_os(key).is_none())
+        .map(|(key, var)| (key.clone(), var.value.clone()))
+        .collect();
+    vars.extend(params.iter().map(|(k, v)| (k.clone(), v.clone())));
+    vars
+}
@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// File format of a stored configuration, chosen by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Every supported format, in the order lookups try them
+    pub const ALL: [ConfigFormat; 3] = [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+        }
+    }
+
+    /// Format of a configuration file, or `None` for other files such as `.env`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == ext)
+    }
+
+    /// Parse file contents into a JSON value, so migrations work the same for every format
+    pub fn parse(self, content: &str) -> Result<Value> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(anyhow::Error::from),
+            ConfigFormat::Toml => toml::from_str(content).map_err(anyhow::Error::from),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(anyhow::Error::from),
+        }
+        .context("Failed to deserialize configuration")
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String> {
+        match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(value).map_err(anyhow::Error::from),
+            ConfigFormat::Toml => toml::to_string_pretty(value).map_err(anyhow::Error::from),
+            ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(anyhow::Error::from),
+        }
+        .context("Failed to serialize configuration")
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            _ => Err(format!(
+                "unknown format '{}': expected json, toml or yaml",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::{QemuConfig, config_file, read_config};
+use crate::utils::qemu::merge_items;
+use crate::utils::qemu_args::{QemuArg, QemuArgs};
+
+/// A configuration with its `extends` chain applied
+#[derive(Debug, Clone)]
+pub struct Inherited {
+    pub config: QemuConfig,
+    /// The configuration's own name followed by its ancestors, nearest first
+    pub chain: Vec<String>,
+    /// Every merged argument with the configuration it came from
+    pub args: Vec<InheritedArg>,
+}
+
+/// One option (or positional argument) of a merged configuration
+#[derive(Debug, Clone)]
+pub struct InheritedArg {
+    pub arg: QemuArg,
+    /// Name of the configuration that contributed it
+    pub source: String,
+}
+
+impl InheritedArg {
+    fn name(&self) -> Option<&str> {
+        self.arg.name()
+    }
+}
+
+/// Apply the `extends` chain of configuration `name`, loading its ancestors from disk.
+///
+/// Each child's `remove_args`, `replace_args` and `args` are applied in that
+/// order on top of its parent's merged arguments.
+pub fn resolve_extends(name: &str, config: QemuConfig) -> Result<Inherited> {
+    let mut chain = vec![name.to_string()];
+    let mut configs = vec![config];
+    while let Some(parent) = configs.last().and_then(|config| config.extends.clone()) {
+        if let Some(start) = chain.iter().position(|seen| *seen == parent) {
+            let mut cycle = chain[start..].to_vec();
+            cycle.push(parent);
+            anyhow::bail!("Configuration inheritance cycle: {}", cycle.join(" -> "));
+        }
+        let parent_path = config_file(&parent)?;
+        if !parent_path.exists() {
+            anyhow::bail!(
+                "Configuration '{}' extends '{}', which does not exist",
+                chain[chain.len() - 1],
+                parent
+            );
+        }
+        configs.push(read_config(&parent, &parent_path)?);
+        chain.push(parent);
+    }
+
+    // Ancestors may be saved from other directories than the configuration runs
+    // from, so their relative paths are pinned to their own working directory.
+    // Removals are compared against those pinned paths.
+    for (level, config) in configs.iter_mut().enumerate() {
+        let Some(dir) = config.cwd.clone() else {
+            continue;
+        };
+        if level > 0 {
+            config.args = rebase_args(&config.args, &dir);
+            config.replace_args = rebase_args(&config.replace_args, &dir);
+        }
+        config.remove_args = rebase_args(&config.remove_args, &dir);
+    }
+
+    // Apply from the root ancestor down to the configuration itself
+    let mut levels = chain.iter().zip(configs).rev();
+    let (root_name, mut merged) = levels.next().expect("chain starts with the configuration");
+    let mut args = tag_args(&merged.args, root_name);
+    for (child_name, child) in levels {
+        remove_args(&mut args, &child.remove_args);
+        replace_args(&mut args, tag_args(&child.replace_args, child_name));
+        merge_items(
+            &mut args,
+            tag_args(&child.args, child_name),
+            InheritedArg::name,
+        );
+        merged = inherit_fields(merged, child);
+    }
+
+    merged.args = args.iter().flat_map(|item| item.arg.to_argv()).collect();
+    merged.extends = None;
+    merged.remove_args.clear();
+    merged.replace_args.clear();
+    Ok(Inherited {
+        config: merged,
+        chain,
+        args,
+    })
+}
+
+fn tag_args(args: &[String], source: &str) -> Vec<InheritedArg> {
+    QemuArgs::parse(args)
+        .items
+        .into_iter()
+        .map(|arg| InheritedArg {
+            arg,
+            source: source.to_string(),
+        })
+        .collect()
+}
+
+/// Make the relative file paths in `args` absolute against `dir`
+fn rebase_args(args: &[String], dir: &Path) -> Vec<String> {
+    let mut parsed = QemuArgs::parse(args);
+    for item in &mut parsed.items {
+        item.rebase_paths(dir);
+    }
+    parsed.to_argv()
+}
+
+/// Drop inherited arguments: an option alone removes every occurrence, with a value only exact matches
+fn remove_args(args: &mut Vec<InheritedArg>, removals: &[String]) {
+    for removal in QemuArgs::parse(removals).items {
+        let value = removal.value().map(ToString::to_string);
+        args.retain(|item| match (&removal, &value) {
+            (QemuArg::Positional(_), _) => item.arg != removal,
+            (_, None) => item.name() != removal.name(),
+            (_, Some(value)) => {
+                item.name() != removal.name()
+                    || item.arg.value().map(ToString::to_string).as_ref() != Some(value)
+            }
+        });
+    }
+}
+
+/// Replace every inherited occurrence of an option with the child's, at the first one's position
+fn replace_args(args: &mut Vec<InheritedArg>, replacements: Vec<InheritedArg>) {
+    let mut names: Vec<&str> = Vec::new();
+    for item in &replacements {
+        if let Some(name) = item.name()
+            && !names.contains(&name)
+        {
+            names.push(name);
+        }
+    }
+    for name in names {
+        let position = args
+            .iter()
+            .position(|item| item.name() == Some(name))
+            .unwrap_or(args.len());
+        args.retain(|item| item.name() != Some(name));
+        let new: Vec<_> = replacements
+            .iter()
+            .filter(|item| item.name() == Some(name))
+            .cloned()
+            .collect();
+        args.splice(position..position, new);
+    }
+    // Positional arguments have nothing to replace
+    args.extend(
+        replacements
+            .into_iter()
+            .filter(|item| item.name().is_none()),
+    );
+}
+
+/// Merge everything but the arguments: the child's settings win, lists are combined
+fn inherit_fields(parent: QemuConfig, child: QemuConfig) -> QemuConfig {
+    let mut params = parent.params;
+    params.extend(child.params);
+
+    let mut forwards: Vec<_> = parent
+        .forwards
+        .into_iter()
+        .filter(|forward| {
+            !child
+                .forwards
+                .iter()
+                .any(|own| own.proto == forward.proto && own.guest == forward.guest)
+        })
+        .collect();
+    forwards.extend(child.forwards);
+
+    let mut sidecars: Vec<_> = parent
+        .sidecars
+        .into_iter()
+        .filter(|sidecar| !child.sidecars.iter().any(|own| own.name == sidecar.name))
+        .collect();
+    sidecars.extend(child.sidecars);
+
+    QemuConfig {
+        schema_version: child.schema_version,
+        qemu_bin: if child.qemu_bin.is_empty() {
+            parent.qemu_bin
+        } else {
+            child.qemu_bin
+        },
+        args: Vec::new(),
+        desc: child.desc.or(parent.desc),
+        qemu_version: child.qemu_version.or(parent.qemu_version),
+        extends: child.extends,
+        remove_args: child.remove_args,
+        replace_args: child.replace_args,
+        gdb_port: child.gdb_port.or(parent.gdb_port),
+        params,
+        forwards,
+        ssh: child.ssh.or(parent.ssh),
+        cwd: child.cwd.or(parent.cwd),
+        sidecars,
+        pre_exec: [parent.pre_exec, child.pre_exec].concat(),
+        post_exec: [parent.post_exec, child.post_exec].concat(),
+    }
+}
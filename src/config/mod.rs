@@ -1,7 +1,22 @@
+pub mod env;
+pub mod format;
+pub mod inherit;
+pub mod params;
 pub mod storage;
 pub mod types;
 pub mod validation;
 
-pub use storage::{config_dir, config_file};
-pub use types::QemuConfig;
+pub use env::{EnvFileVar, load_env_files, merge_variables, parse_env_file};
+pub use format::ConfigFormat;
+pub use inherit::{Inherited, InheritedArg, resolve_extends};
+pub use params::{check_defaults, resolve_params};
+pub use storage::{
+    SCHEMA_VERSION, config_children, config_dir, config_env_file, config_file, config_file_as,
+    config_files, config_names, file_names, load_config, load_inherited, migrate_config_file,
+    parse_config, read_config, state_dir, write_config,
+};
+pub use types::{
+    GdbPort, ParamDecl, ParamSpec, ParamType, PortForward, Protocol, QemuConfig, SidecarSpec,
+    SshSettings,
+};
 pub use validation::validate_config;
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::{ParamType, QemuConfig};
+
+/// Resolve declared parameters from `--set` values and defaults, validating their types
+pub fn resolve_params(
+    config: &QemuConfig,
+    sets: &[(String, String)],
+) -> Result<BTreeMap<String, String>> {
+    for (name, _) in sets {
+        if !config.params.contains_key(name) {
+            let declared: Vec<&str> = config.params.keys().map(String::as_str).collect();
+            if declared.is_empty() {
+                anyhow::bail!(
+                    "Unknown parameter '{}': configuration declares no parameters",
+                    name
+                );
+            }
+            anyhow::bail!(
+                "Unknown parameter '{}'. Declared parameters: {}",
+                name,
+                declared.join(", ")
+            );
+        }
+    }
+
+    let mut values = BTreeMap::new();
+    for (name, spec) in &config.params {
+        // The last --set for a name wins
+        let given = sets.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v);
+        let Some(value) = given.or(spec.default.as_ref()) else {
+            anyhow::bail!(
+                "Parameter '{}' has no value. Pass it with '--set {}=<{}>'",
+                name,
+                name,
+                spec.kind
+            );
+        };
+        check_type(name, spec.kind, value, config.cwd.as_deref())?;
+        values.insert(name.clone(), value.clone());
+    }
+
+    Ok(values)
+}
+
+/// Check the declared defaults when saving a configuration.
+///
+/// A missing default path only warns, since it may be created before the first run.
+pub fn check_defaults(config: &QemuConfig) -> Result<()> {
+    for (name, spec) in &config.params {
+        let Some(default) = &spec.default else {
+            continue;
+        };
+        if let Err(err) = check_type(name, spec.kind, default, config.cwd.as_deref()) {
+            if spec.kind != ParamType::Path {
+                return Err(err.context("Invalid parameter default"));
+            }
+            eprintln!("WARNING: {:#}", err);
+        }
+    }
+    Ok(())
+}
+
+/// Check that a parameter value matches its declared type, resolving relative paths against `cwd`
+fn check_type(name: &str, kind: ParamType, value: &str, cwd: Option<&Path>) -> Result<()> {
+    let valid = match kind {
+        ParamType::String => true,
+        ParamType::Int => value.parse::<i64>().is_ok(),
+        ParamType::Size => Regex::new(r"^\d+(\.\d+)?[kKmMgGtTpPeE]?[bB]?$")
+            .unwrap()
+            .is_match(value),
+        ParamType::Path => {
+            let path = cwd.map_or_else(|| Path::new(value).to_path_buf(), |dir| dir.join(value));
+            if !path.exists() {
+                anyhow::bail!("Parameter '{}': path '{}' does not exist", name, value);
+            }
+            true
+        }
+    };
+    if !valid {
+        anyhow::bail!(
+            "Parameter '{}': '{}' is not a valid {} value",
+            name,
+            value,
+            kind
+        );
+    }
+    Ok(())
+}
+
+/// Parse a `--set NAME=VALUE` argument
+pub fn parse_set(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("invalid --set '{}': expected NAME=VALUE", s)),
+    }
+}
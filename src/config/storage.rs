@@ -1,6 +1,16 @@
 use anyhow::{Context, Result};
+use serde_json::{Map, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ConfigFormat, Inherited, QemuConfig, resolve_extends};
+use crate::error::VexError;
+
+/// Schema version of the configurations this Vex writes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Upgrade steps, where `MIGRATIONS[n]` turns version `n` into version `n + 1` and stamps it
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_v0_to_v1];
 
 /// Get Vex config file storage directory (default ~/.vex/configs)
 pub fn config_dir() -> Result<PathBuf> {
@@ -16,8 +26,209 @@ pub fn config_dir() -> Result<PathBuf> {
     Ok(dir)
 }
 
-/// Get path to the config file for a given name
+/// Get path to the config file for a given name.
+///
+/// This is the existing file in whichever format it was saved, or `<name>.json`
+/// for a new configuration. A name saved in several formats is an error.
 pub fn config_file(name: &str) -> Result<PathBuf> {
+    let mut files = config_files(name)?;
+    match files.len() {
+        0 => config_file_as(name, ConfigFormat::Json),
+        1 => Ok(files.remove(0)),
+        _ => anyhow::bail!(
+            "Configuration '{}' exists in several formats ({}). Delete all but one, or remove them all with 'vex rm'",
+            name,
+            file_names(&files)
+        ),
+    }
+}
+
+/// Get path to the config file for a given name in a specific format
+pub fn config_file_as(name: &str, format: ConfigFormat) -> Result<PathBuf> {
+    let dir = config_dir()?;
+    Ok(dir.join(format!("{}.{}", name, format.extension())))
+}
+
+/// Existing config files for a name, one per format it is saved in
+pub fn config_files(name: &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for format in ConfigFormat::ALL {
+        let path = config_file_as(name, format)?;
+        if path.exists() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// File names of `paths`, comma-separated for messages
+pub fn file_names(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Names of all saved configurations in any format, sorted and without duplicates
+pub fn config_names() -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(config_dir()?).context("Failed to read config directory")? {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if ConfigFormat::from_path(&path).is_some()
+            && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+        {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// Names of the saved configurations that extend `name` directly
+pub fn config_children(name: &str) -> Result<Vec<String>> {
+    let mut children = Vec::new();
+    for child in config_names()? {
+        if let Ok(path) = config_file(&child)
+            && let Ok(config) = read_config(&child, &path)
+            && config.extends.as_deref() == Some(name)
+        {
+            children.push(child);
+        }
+    }
+    Ok(children)
+}
+
+/// Get path to the optional env file stored next to a config file
+pub fn config_env_file(name: &str) -> Result<PathBuf> {
     let dir = config_dir()?;
-    Ok(dir.join(format!("{}.json", name)))
+    Ok(dir.join(format!("{}.env", name)))
+}
+
+/// Get Vex runtime state directory, next to the config directory (default ~/.vex/state)
+pub fn state_dir() -> Result<PathBuf> {
+    let config_dir = config_dir()?;
+    let dir = match config_dir.parent() {
+        Some(parent) => parent.join("state"),
+        None => config_dir.join(".state"),
+    };
+
+    fs::create_dir_all(&dir).context("Failed to create state directory")?;
+    Ok(dir)
+}
+
+/// Load a saved configuration by name
+pub fn load_config(name: &str) -> Result<QemuConfig> {
+    Ok(load_inherited(name)?.config)
+}
+
+/// Load a saved configuration by name, keeping where each inherited argument came from
+pub fn load_inherited(name: &str) -> Result<Inherited> {
+    let config_path = config_file(name)?;
+    if !config_path.exists() {
+        anyhow::bail!(
+            "Configuration '{}' does not exist. Use 'vex list' to see available configurations",
+            name
+        );
+    }
+
+    resolve_extends(name, read_config(name, &config_path)?)
+}
+
+/// Read a configuration file, migrating it in memory if it uses an older schema
+pub fn read_config(name: &str, path: &Path) -> Result<QemuConfig> {
+    let content = fs::read_to_string(path).context("Failed to read config file")?;
+    parse_config(name, &content, path_format(path)).map(|(config, _)| config)
+}
+
+/// Parse a stored configuration, returning it with the schema version it was stored with
+pub fn parse_config(name: &str, content: &str, format: ConfigFormat) -> Result<(QemuConfig, u32)> {
+    let mut value = format.parse(content)?;
+    let version = migrate(name, &mut value)?;
+    let config = serde_json::from_value(value).context("Failed to deserialize configuration")?;
+    Ok((config, version))
+}
+
+/// Write a configuration file at the current schema version, in the format of its extension
+pub fn write_config(path: &Path, config: &QemuConfig) -> Result<()> {
+    let config = QemuConfig {
+        schema_version: SCHEMA_VERSION,
+        ..config.clone()
+    };
+    let content = path_format(path).serialize(&config)?;
+    fs::write(path, content).context("Failed to save config file")
+}
+
+/// Format of a config file path; paths Vex builds always have a known extension
+fn path_format(path: &Path) -> ConfigFormat {
+    ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Json)
+}
+
+/// Rewrite an older configuration file at the current schema version.
+///
+/// The original is kept as `<file>.v<old version>.bak`. Returns the old version
+/// and the backup path, or `None` when the file is already up to date.
+pub fn migrate_config_file(name: &str, path: &Path) -> Result<Option<(u32, PathBuf)>> {
+    let content = fs::read_to_string(path).context("Failed to read config file")?;
+    let (config, version) = parse_config(name, &content, path_format(path))?;
+    if version == SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", version));
+    let backup = PathBuf::from(backup);
+    fs::write(&backup, &content)
+        .with_context(|| format!("Failed to write backup {}", backup.display()))?;
+    write_config(path, &config)?;
+    Ok(Some((version, backup)))
+}
+
+/// Bring a stored configuration up to the current schema, returning its original version
+fn migrate(name: &str, value: &mut Value) -> Result<u32> {
+    let config = value
+        .as_object_mut()
+        .context("Failed to deserialize configuration: expected a JSON object")?;
+    let version = match config.get("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("Invalid schema_version {} in configuration", version))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(VexError::UnsupportedSchemaVersion {
+            name: name.to_string(),
+            version,
+            supported: SCHEMA_VERSION,
+        }
+        .into());
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(config);
+    }
+    Ok(version)
+}
+
+/// Version 0 is every file saved before versioning; all later fields have defaults.
+///
+/// Hand-written files could give `gdb_port` as a string such as `"1234"` or `"AUTO"`;
+/// version 1 stores a number or `"auto"`, as Vex writes it.
+fn migrate_v0_to_v1(config: &mut Map<String, Value>) {
+    if let Some(Value::String(port)) = config.get_mut("gdb_port") {
+        let port = port.trim();
+        let normalized = if port.eq_ignore_ascii_case("auto") {
+            Value::from("auto")
+        } else if let Ok(number) = port.parse::<u16>() {
+            Value::from(number)
+        } else {
+            // Left as is, so loading reports the invalid port
+            Value::from(port)
+        };
+        config.insert("gdb_port".to_string(), normalized);
+    }
+    config.insert("schema_version".to_string(), 1.into());
 }
@@ -1,14 +1,374 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::config::storage::SCHEMA_VERSION;
+use crate::utils::shell::shell_split;
 
 /// Stored QEMU configuration structure
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QemuConfig {
-    /// Path to QEMU executable
+    /// Schema version the configuration was stored with
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
+    /// Path to QEMU executable; empty inherits it from `extends`
+    #[serde(default)]
     pub qemu_bin: String,
-    /// List of QEMU startup arguments
+    /// List of QEMU startup arguments, merged into the parent's when extending
     pub args: Vec<String>,
     /// Configuration description (optional)
     pub desc: Option<String>,
     /// QEMU version detected at save time
     pub qemu_version: Option<String>,
+    /// Parent configuration this one builds on, resolved when loading
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Parent arguments to drop: an option alone drops all of its occurrences
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_args: Vec<String>,
+    /// Arguments replacing every parent occurrence of the same options
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replace_args: Vec<String>,
+    /// Default GDB stub port used by `vex exec -d` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gdb_port: Option<GdbPort>,
+    /// Declared parameters referenced from args as `${name}`, keyed by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, ParamSpec>,
+    /// Host-to-guest port forwards added to the user-mode network
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forwards: Vec<PortForward>,
+    /// Login settings for `vex ssh`; guest port 22 is forwarded automatically when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshSettings>,
+    /// Working directory QEMU runs from, recorded at save time (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Helper daemons started before QEMU and stopped after it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<SidecarSpec>,
+    /// Shell commands run before QEMU starts, with parameters in the environment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_exec: Vec<String>,
+    /// Shell commands run after QEMU exits, even when it failed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_exec: Vec<String>,
+}
+
+/// Configurations built in memory, such as inline compose VMs, use the current schema
+fn current_schema_version() -> u32 {
+    SCHEMA_VERSION
+}
+
+/// Login settings used by `vex ssh`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SshSettings {
+    /// Guest user to log in as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Private key file passed to `ssh -i`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+/// Helper daemon such as virtiofsd or swtpm, given on the command line as `NAME=COMMAND`.
+///
+/// `${socket}` in the command is the socket the sidecar must create; QEMU
+/// arguments refer to it as `${sidecar.NAME}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarSpec {
+    pub name: String,
+    /// Program and arguments
+    pub command: Vec<String>,
+    /// Seconds to wait for the socket to appear (default 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl FromStr for SidecarSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, command) = s
+            .split_once('=')
+            .ok_or_else(|| format!("invalid sidecar '{}': expected NAME=COMMAND", s))?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!(
+                "invalid sidecar name '{}': use letters, digits and '_'",
+                name
+            ));
+        }
+        let command = shell_split(command)?;
+        if command.is_empty() {
+            return Err(format!("sidecar '{}' has an empty command", name));
+        }
+        Ok(SidecarSpec {
+            name: name.to_string(),
+            command,
+            timeout: None,
+        })
+    }
+}
+
+/// Declaration of a named configuration parameter
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParamSpec {
+    /// Value type used to validate supplied values
+    #[serde(rename = "type", default)]
+    pub kind: ParamType,
+    /// Value used when none is given with `vex exec --set` (required if absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Help text shown by `vex print`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+/// Type of a configuration parameter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    /// Any text
+    #[default]
+    String,
+    /// Path to an existing file or directory
+    Path,
+    /// Memory size such as `512M` or `4G`
+    Size,
+    /// Integer
+    Int,
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ParamType::String => "string",
+            ParamType::Path => "path",
+            ParamType::Size => "size",
+            ParamType::Int => "int",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ParamType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "string" | "str" => Ok(ParamType::String),
+            "path" => Ok(ParamType::Path),
+            "size" => Ok(ParamType::Size),
+            "int" => Ok(ParamType::Int),
+            other => Err(format!(
+                "unknown parameter type '{}': expected string, path, size or int",
+                other
+            )),
+        }
+    }
+}
+
+/// Parameter declaration given on the command line as `NAME[:TYPE][=DEFAULT][#HELP]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamDecl {
+    pub name: String,
+    pub spec: ParamSpec,
+}
+
+impl FromStr for ParamDecl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, help) = match s.split_once('#') {
+            Some((decl, help)) => (decl, Some(help.trim().to_string())),
+            None => (s, None),
+        };
+        let (head, default) = match s.split_once('=') {
+            Some((head, default)) => (head, Some(default.to_string())),
+            None => (s, None),
+        };
+        let (name, kind) = match head.split_once(':') {
+            Some((name, kind)) => (name, kind.parse()?),
+            None => (head, ParamType::String),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!(
+                "invalid parameter name '{}': use letters, digits and '_'",
+                name
+            ));
+        }
+        Ok(ParamDecl {
+            name: name.to_string(),
+            spec: ParamSpec {
+                kind,
+                default,
+                help: help.filter(|help| !help.is_empty()),
+            },
+        })
+    }
+}
+
+/// GDB stub port selection: a fixed port number or `auto` for a free port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawGdbPort", into = "RawGdbPort")]
+pub enum GdbPort {
+    Auto,
+    Fixed(u16),
+}
+
+impl fmt::Display for GdbPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GdbPort::Auto => write!(f, "auto"),
+            GdbPort::Fixed(port) => write!(f, "{}", port),
+        }
+    }
+}
+
+impl FromStr for GdbPort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(GdbPort::Auto);
+        }
+        match s.parse::<u16>() {
+            Ok(0) | Err(_) => Err(format!(
+                "invalid GDB port '{}': expected a port number (1-65535) or 'auto'",
+                s
+            )),
+            Ok(port) => Ok(GdbPort::Fixed(port)),
+        }
+    }
+}
+
+/// On-disk form of `GdbPort`, accepting either a number or a string
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawGdbPort {
+    Number(u16),
+    Text(String),
+}
+
+impl TryFrom<RawGdbPort> for GdbPort {
+    type Error = String;
+
+    fn try_from(raw: RawGdbPort) -> Result<Self, Self::Error> {
+        match raw {
+            RawGdbPort::Number(port) => GdbPort::from_str(&port.to_string()),
+            RawGdbPort::Text(text) => GdbPort::from_str(&text),
+        }
+    }
+}
+
+impl From<GdbPort> for RawGdbPort {
+    fn from(port: GdbPort) -> Self {
+        match port {
+            GdbPort::Auto => RawGdbPort::Text("auto".to_string()),
+            GdbPort::Fixed(port) => RawGdbPort::Number(port),
+        }
+    }
+}
+
+/// Transport protocol of a port forward
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Tcp => write!(f, "tcp"),
+            Protocol::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+/// Host-to-guest port forward, written as `[tcp|udp:]HOST:GUEST` with `auto` as HOST
+/// to pick a free host port at launch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PortForward {
+    pub proto: Protocol,
+    /// Host port, `None` for `auto`
+    pub host: Option<u16>,
+    pub guest: u16,
+}
+
+impl fmt::Display for PortForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.host {
+            Some(host) => write!(f, "{}:{}:{}", self.proto, host, self.guest),
+            None => write!(f, "{}:auto:{}", self.proto, self.guest),
+        }
+    }
+}
+
+impl PortForward {
+    /// QEMU `hostfwd=` value, or `None` while the host port is still `auto`
+    pub fn hostfwd(&self) -> Option<String> {
+        self.host
+            .map(|host| format!("{}::{}-:{}", self.proto, host, self.guest))
+    }
+}
+
+impl FromStr for PortForward {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let (proto, host, guest) = match parts[..] {
+            [host, guest] => (Protocol::Tcp, host, guest),
+            [proto, host, guest] => {
+                let proto = match proto.to_ascii_lowercase().as_str() {
+                    "tcp" => Protocol::Tcp,
+                    "udp" => Protocol::Udp,
+                    _ => return Err(format!("unknown protocol '{}': expected tcp or udp", proto)),
+                };
+                (proto, host, guest)
+            }
+            _ => {
+                return Err(format!(
+                    "invalid port forward '{}': expected [tcp|udp:]HOST:GUEST",
+                    s
+                ));
+            }
+        };
+        let parse_port = |port: &str| match port.parse::<u16>() {
+            Ok(0) | Err(_) => Err(format!(
+                "invalid port '{}' in forward '{}': expected 1-65535",
+                port, s
+            )),
+            Ok(port) => Ok(port),
+        };
+        let host = if host.eq_ignore_ascii_case("auto") {
+            None
+        } else {
+            Some(parse_port(host)?)
+        };
+        Ok(PortForward {
+            proto,
+            host,
+            guest: parse_port(guest)?,
+        })
+    }
+}
+
+impl TryFrom<String> for PortForward {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PortForward> for String {
+    fn from(forward: PortForward) -> Self {
+        forward.to_string()
+    }
 }
@@ -1,4 +1,5 @@
 use crate::config::QemuConfig;
+use crate::utils::qemu_options::tokenize;
 use anyhow::Result;
 
 /// Validate QEMU configuration
@@ -7,8 +8,20 @@ pub fn validate_config(config: &QemuConfig) -> Result<()> {
         anyhow::bail!("QEMU binary path cannot be empty");
     }
 
-    // Additional validation logic can be added here
-    // For example: check if qemu_bin exists, validate args format, etc.
+    for token in tokenize(&config.args) {
+        let (Some(option), Some(spec)) = (token.option, token.spec()) else {
+            continue;
+        };
+        if spec.takes_arg && token.value.is_none() {
+            anyhow::bail!("QEMU option '{}' requires a value", option);
+        }
+        if spec.deprecated {
+            eprintln!(
+                "WARNING: QEMU option '{}' is deprecated and may be removed in newer QEMU versions",
+                option
+            );
+        }
+    }
 
     Ok(())
 }
@@ -8,6 +8,17 @@ pub enum VexError {
     InvalidConfig(String),
     IoError(std::io::Error),
     SerializationError(serde_json::Error),
+    /// A configuration was written by a newer Vex with a schema this one can't read
+    UnsupportedSchemaVersion {
+        name: String,
+        version: u32,
+        supported: u32,
+    },
+    /// Vex should exit with this status after printing the message (e.g. QEMU's own exit code)
+    Exit {
+        code: u8,
+        message: String,
+    },
 }
 
 impl fmt::Display for VexError {
@@ -20,6 +31,16 @@ impl fmt::Display for VexError {
             VexError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
             VexError::IoError(err) => write!(f, "IO error: {}", err),
             VexError::SerializationError(err) => write!(f, "Serialization error: {}", err),
+            VexError::UnsupportedSchemaVersion {
+                name,
+                version,
+                supported,
+            } => write!(
+                f,
+                "Configuration '{}' uses schema version {}, but this Vex only supports up to version {}. Upgrade Vex to use it",
+                name, version, supported
+            ),
+            VexError::Exit { message, .. } => write!(f, "{}", message),
         }
     }
 }
@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use crate::config::state_dir;
+use crate::utils::sidecar::SidecarRun;
+
+/// Record of one `vex exec` invocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Sequential run ID, usable with `vex exec --rerun`
+    pub id: u64,
+    /// Name of the configuration that was executed
+    pub config: String,
+    /// Path to QEMU executable after substitution
+    pub qemu_bin: String,
+    /// Final QEMU arguments after substitution, without the per-run QMP socket
+    pub args: Vec<String>,
+    /// Start time in seconds since the Unix epoch
+    pub started_at: u64,
+    /// Wall-clock run time in seconds (`None` for detached runs)
+    pub duration_secs: Option<u64>,
+    /// Exit code vex reported (`None` for detached runs or launch failures)
+    pub exit_code: Option<u8>,
+    /// Whether the run was started in debug mode
+    pub debug: bool,
+    /// Whether QEMU was started in the background
+    #[serde(default)]
+    pub detached: bool,
+    /// GDB server port when started in debug mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gdb_port: Option<u16>,
+    /// Resolved parameter values, passed to the hooks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    /// Pre-exec hooks run before QEMU started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_exec: Vec<String>,
+    /// Post-exec hooks run after QEMU exited
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_exec: Vec<String>,
+    /// Sidecars started for the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<SidecarRun>,
+    /// Working directory QEMU ran from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Instance ID QEMU ran as (`None` if it never started)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Run log of QEMU and its sidecars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<PathBuf>,
+}
+
+/// Get the run history file (default ~/.vex/state/history.jsonl)
+pub fn history_file() -> Result<PathBuf> {
+    let dir = state_dir()?;
+    fs::create_dir_all(&dir).context("Failed to create state directory")?;
+    Ok(dir.join("history.jsonl"))
+}
+
+/// Load all run records, oldest first
+pub fn load_history() -> Result<Vec<RunRecord>> {
+    let path = history_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).context("Failed to read run history")?;
+    Ok(parse_history(&content))
+}
+
+/// Parse history lines, skipping ones that can't be parsed, e.g. a record cut short by a crash
+fn parse_history(content: &str) -> Vec<RunRecord> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Append a run record, assigning it the next run ID
+pub fn append_history(mut record: RunRecord) -> Result<RunRecord> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(history_file()?)
+        .context("Failed to open run history")?;
+    // Concurrent runs (e.g. the VMs of `vex up`) must not pick the same ID, so the
+    // file stays locked from reading the last ID to writing the record
+    file.lock().context("Failed to lock run history")?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .context("Failed to read run history")?;
+    record.id = parse_history(&content)
+        .iter()
+        .map(|record| record.id)
+        .max()
+        .map_or(1, |id| id + 1);
+    let line = serde_json::to_string(&record).context("Failed to serialize run record")?;
+    file.write_all(format!("{}\n", line).as_bytes())
+        .context("Failed to write run history")?;
+    Ok(record)
+}
+
+/// Find a run record by its ID
+pub fn find_run(id: u64) -> Result<RunRecord> {
+    load_history()?
+        .into_iter()
+        .find(|record| record.id == id)
+        .with_context(|| format!("No run #{} in history. Use 'vex history' to list runs", id))
+}
+
+/// Find the latest run of an instance ID, which outlives the instance record
+pub fn find_instance_run(id: &str) -> Result<Option<RunRecord>> {
+    Ok(load_history()?
+        .into_iter()
+        .rev()
+        .find(|record| record.instance.as_deref() == Some(id)))
+}
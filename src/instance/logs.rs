@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::state_dir;
+use crate::instance::types::unix_now;
+use crate::instance::{Instance, list_instances};
+use crate::utils::time::file_timestamp;
+
+/// Number of run logs kept per configuration unless `VEX_KEEP_LOGS` says otherwise
+const DEFAULT_KEEP_LOGS: usize = 20;
+
+/// Get the directory holding run logs of a configuration (default ~/.vex/state/logs/<name>)
+pub fn run_logs_dir(config: &str) -> Result<PathBuf> {
+    let dir = state_dir()?.join("logs").join(config);
+    fs::create_dir_all(&dir).context("Failed to create logs directory")?;
+    Ok(dir)
+}
+
+/// Create a new timestamped log file for a run of a configuration
+pub fn create_run_log(config: &str) -> Result<(PathBuf, fs::File)> {
+    let name = format!("{}-{}.log", file_timestamp(unix_now()), std::process::id());
+    let path = run_logs_dir(config)?.join(name);
+    let file = fs::File::create(&path)
+        .with_context(|| format!("Failed to create log file {}", path.display()))?;
+    Ok((path, file))
+}
+
+/// List the run logs of a configuration, oldest first
+pub fn list_run_logs(config: &str) -> Result<Vec<PathBuf>> {
+    let dir = state_dir()?.join("logs").join(config);
+    let mut logs = Vec::new();
+    if !dir.exists() {
+        return Ok(logs);
+    }
+    for entry in fs::read_dir(&dir).context("Failed to read logs directory")? {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if path.extension().is_some_and(|ext| ext == "log") {
+            logs.push(path);
+        }
+    }
+    // File names start with a sortable timestamp
+    logs.sort();
+    Ok(logs)
+}
+
+/// Start time of a run, taken from its log file name (`20240131-134500-<pid>.log`)
+pub fn run_log_started(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let (date, rest) = stem.split_once('-')?;
+    let time = rest.split('-').next()?;
+    if date.len() != 8 || time.len() != 6 {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{} {}:{}:{}",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    ))
+}
+
+/// Delete the oldest run logs of a configuration beyond the retention count.
+///
+/// The log of the run being started (`current`) and logs still written to by
+/// running instances are kept even when that exceeds the count.
+pub fn prune_run_logs(config: &str, current: &Path) -> Result<()> {
+    let keep = keep_logs();
+    let logs = list_run_logs(config)?;
+    let live: Vec<PathBuf> = list_instances()?
+        .into_iter()
+        .filter(Instance::is_running)
+        .filter_map(|instance| instance.log_file)
+        .collect();
+    let excess = logs.len().saturating_sub(keep);
+    let old_logs = logs
+        .iter()
+        .filter(|path| path.as_path() != current && !live.contains(path))
+        .take(excess);
+    for path in old_logs {
+        fs::remove_file(path)
+            .with_context(|| format!("Failed to delete old log file {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Number of run logs to keep per configuration, from `VEX_KEEP_LOGS`
+fn keep_logs() -> usize {
+    let Ok(value) = std::env::var("VEX_KEEP_LOGS") else {
+        return DEFAULT_KEEP_LOGS;
+    };
+    match value.parse::<usize>() {
+        Ok(0) => {
+            eprintln!("WARNING: VEX_KEEP_LOGS must be at least 1, keeping only the latest log");
+            1
+        }
+        Ok(keep) => keep,
+        Err(_) => {
+            eprintln!(
+                "WARNING: Invalid VEX_KEEP_LOGS '{}', keeping the latest {} logs",
+                value, DEFAULT_KEEP_LOGS
+            );
+            DEFAULT_KEEP_LOGS
+        }
+    }
+}
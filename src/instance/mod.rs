@@ -0,0 +1,12 @@
+pub mod history;
+pub mod logs;
+pub mod storage;
+pub mod types;
+
+pub use history::{RunRecord, append_history, find_instance_run, find_run, load_history};
+pub use logs::{create_run_log, list_run_logs, prune_run_logs, run_log_started, run_logs_dir};
+pub use storage::{
+    find_instance, find_running_instance, finish_instance, instances_dir, list_instances,
+    prune_stale_instances, remove_instance, save_instance,
+};
+pub use types::Instance;
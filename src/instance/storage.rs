@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::state_dir;
+use crate::instance::Instance;
+use crate::utils::hooks::run_post_hooks;
+use crate::utils::sidecar::stop_sidecars;
+
+/// Get the directory holding instance records and logs (default ~/.vex/state/instances)
+pub fn instances_dir() -> Result<PathBuf> {
+    let dir = state_dir()?.join("instances");
+    fs::create_dir_all(&dir).context("Failed to create instances directory")?;
+    Ok(dir)
+}
+
+/// Get path to the record file for a given instance ID
+pub fn instance_file(id: &str) -> Result<PathBuf> {
+    Ok(instances_dir()?.join(format!("{}.json", id)))
+}
+
+/// Persist an instance record
+pub fn save_instance(instance: &Instance) -> Result<()> {
+    let path = instance_file(&instance.id)?;
+    let json =
+        serde_json::to_string_pretty(instance).context("Failed to serialize instance record")?;
+    fs::write(&path, json).context("Failed to save instance record")?;
+    Ok(())
+}
+
+/// Delete an instance record and its sockets, keeping its log file
+pub fn remove_instance(instance: &Instance) -> Result<()> {
+    for socket in instance.sockets.values() {
+        // QEMU usually removes its own sockets on clean exit
+        let _ = fs::remove_file(socket);
+    }
+    let path = instance_file(&instance.id)?;
+    if path.exists() {
+        fs::remove_file(&path).context("Failed to delete instance record")?;
+    }
+    Ok(())
+}
+
+/// Clean up after an instance whose QEMU has exited: stop its sidecars, delete
+/// its record and run its post-exec hooks.
+///
+/// The record is claimed by renaming it first, so concurrent Vex commands finish an
+/// instance only once. Returns `false` when another command claimed it first.
+pub fn finish_instance(instance: &Instance) -> Result<bool> {
+    let path = instance_file(&instance.id)?;
+    let claimed = path.with_extension("finishing");
+    if fs::rename(&path, &claimed).is_err() {
+        return Ok(false);
+    }
+    stop_sidecars(&instance.live_sidecars());
+    remove_instance(instance)?;
+    fs::remove_file(&claimed).context("Failed to delete instance record")?;
+    run_post_hooks(
+        &instance.post_exec,
+        &instance.hook_env,
+        instance.cwd.as_deref(),
+    )?;
+    Ok(true)
+}
+
+/// Load all instance records, oldest first
+pub fn list_instances() -> Result<Vec<Instance>> {
+    let dir = instances_dir()?;
+    let entries = fs::read_dir(&dir).context("Failed to read instances directory")?;
+    let mut instances = Vec::new();
+
+    for entry in entries {
+        let entry = entry.context("Failed to read directory entry")?;
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            // Skip unreadable or corrupt records
+            if let Ok(content) = fs::read_to_string(&path)
+                && let Ok(instance) = serde_json::from_str::<Instance>(&content)
+            {
+                instances.push(instance);
+            }
+        }
+    }
+
+    instances.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+    Ok(instances)
+}
+
+/// Finish instances whose QEMU process is no longer running, returning them.
+///
+/// Detached instances can exit on their own, e.g. on guest poweroff, and still
+/// need their sidecars stopped and post-exec hooks run. With a `query`, only the
+/// instances with that ID or configuration name are finished.
+pub fn prune_stale_instances(query: Option<&str>) -> Result<Vec<Instance>> {
+    let mut stale = Vec::new();
+    for instance in list_instances()? {
+        if instance.is_running()
+            || query.is_some_and(|query| instance.id != query && instance.config != query)
+        {
+            continue;
+        }
+        match finish_instance(&instance) {
+            Ok(true) => {}
+            // Another Vex command is finishing it
+            Ok(false) => continue,
+            Err(err) => eprintln!(
+                "WARNING: Cleaning up after instance '{}' failed: {:#}",
+                instance.id, err
+            ),
+        }
+        stale.push(instance);
+    }
+    Ok(stale)
+}
+
+/// Find a running instance by instance ID or by configuration name.
+///
+/// A configuration name only matches when exactly one instance of it is running.
+pub fn find_instance(query: &str) -> Result<Instance> {
+    find_running_instance(query)?.with_context(|| {
+        format!(
+            "No running instance '{}'. Use 'vex ps' to see running instances",
+            query
+        )
+    })
+}
+
+/// Like `find_instance`, but returns `None` when nothing matches.
+///
+/// Records of instances that exited are skipped but left for `vex ps`, `vex stop`
+/// or `vex kill` to finish, so looking up one instance never runs hooks of another.
+pub fn find_running_instance(query: &str) -> Result<Option<Instance>> {
+    let instances: Vec<Instance> = list_instances()?
+        .into_iter()
+        .filter(Instance::is_running)
+        .collect();
+
+    if let Some(instance) = instances.iter().find(|i| i.id == query) {
+        return Ok(Some(instance.clone()));
+    }
+
+    let mut matches: Vec<&Instance> = instances.iter().filter(|i| i.config == query).collect();
+    match matches.len() {
+        0 => Ok(None),
+        1 => Ok(Some(matches.remove(0).clone())),
+        _ => {
+            let ids: Vec<&str> = matches.iter().map(|i| i.id.as_str()).collect();
+            anyhow::bail!(
+                "Configuration '{}' has several running instances ({}), specify an instance ID",
+                query,
+                ids.join(", ")
+            )
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utils::process::{is_same_process, send_signal};
+
+/// Record of a running QEMU instance started by `vex exec`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instance {
+    /// Unique instance ID (`<config>-<pid>`)
+    pub id: String,
+    /// Name of the configuration the instance was started from
+    pub config: String,
+    /// Process ID of the QEMU process
+    pub pid: u32,
+    /// Start time of the QEMU process, to tell it apart from a later process reusing its pid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    /// Path to QEMU executable
+    pub qemu_bin: String,
+    /// Final QEMU arguments the instance was launched with
+    pub args: Vec<String>,
+    /// Start time in seconds since the Unix epoch
+    pub started_at: u64,
+    /// Run log receiving the output of QEMU and its sidecars; it is kept after the instance exits
+    pub log_file: Option<PathBuf>,
+    /// Whether the instance runs in the background
+    #[serde(default)]
+    pub detached: bool,
+    /// GDB server port when started in debug mode
+    pub gdb_port: Option<u16>,
+    /// Control sockets created for the instance, keyed by purpose
+    #[serde(default)]
+    pub sockets: BTreeMap<String, PathBuf>,
+    /// Post-exec hooks to run once a detached instance is stopped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_exec: Vec<String>,
+    /// Environment passed to the post-exec hooks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hook_env: BTreeMap<String, String>,
+    /// Pids of sidecars to stop along with a detached instance, keyed by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sidecars: BTreeMap<String, u32>,
+    /// Start times of the sidecar processes, keyed by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sidecar_start_times: BTreeMap<String, u64>,
+    /// Working directory the instance runs in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+}
+
+impl Instance {
+    /// Build the instance ID for a configuration name and QEMU pid
+    pub fn make_id(config: &str, pid: u32) -> String {
+        format!("{}-{}", config, pid)
+    }
+
+    /// Check whether the recorded QEMU process is still running, and its pid wasn't reused
+    pub fn is_running(&self) -> bool {
+        is_same_process(self.pid, self.start_time)
+    }
+
+    /// Send a signal to QEMU, refusing when its pid no longer belongs to it
+    pub fn signal(&self, signal: libc::c_int) -> Result<()> {
+        if !self.is_running() {
+            anyhow::bail!(
+                "Instance '{}' is no longer running (pid {} is gone or belongs to another process)",
+                self.id,
+                self.pid
+            );
+        }
+        send_signal(self.pid, signal)
+    }
+
+    /// Sidecars that are still the processes started for this instance
+    pub fn live_sidecars(&self) -> BTreeMap<String, u32> {
+        self.sidecars
+            .iter()
+            .filter(|(name, pid)| {
+                is_same_process(**pid, self.sidecar_start_times.get(*name).copied())
+            })
+            .map(|(name, pid)| (name.clone(), *pid))
+            .collect()
+    }
+
+    /// Time elapsed since the instance was started, in seconds
+    pub fn uptime_secs(&self) -> u64 {
+        unix_now().saturating_sub(self.started_at)
+    }
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
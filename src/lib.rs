@@ -1,6 +1,8 @@
 pub mod commands;
+pub mod compose;
 pub mod config;
 pub mod error;
+pub mod instance;
 pub mod utils;
 
 #[cfg(test)]
@@ -11,28 +13,41 @@ use clap::Parser;
 
 use commands::{Cli, Commands};
 use commands::{
-    completions_command, exec_command, list_command, print_command, remove_command, rename_command,
-    save_command,
+    completions_command, convert_command, down_command, edit_command, exec_command,
+    history_command, kill_command, list_command, logs_command, migrate_command, print_command,
+    ps_command, qmp_command, remove_command, rename_command, save_command, snapshot_command,
+    ssh_command, stop_command, up_command,
 };
 
 /// Main application logic
 pub fn run() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
-        Commands::Exec(args) => exec_command(args.name, args.debug, args.full),
+        Commands::Exec(args) => exec_command(args),
+        Commands::History(args) => history_command(args.name, args.full, args.limit),
+        Commands::Ps(_) => ps_command(),
+        Commands::Stop(args) => stop_command(args.instance, args.timeout),
+        Commands::Kill(args) => kill_command(args.instance),
+        Commands::Logs(args) => {
+            logs_command(args.name, args.run, args.list, args.follow, args.lines)
+        }
+        Commands::Ssh(args) => ssh_command(args),
+        Commands::Qmp(args) => {
+            qmp_command(args.instance, args.command, args.arguments, args.events)
+        }
+        Commands::Snapshot(args) => snapshot_command(args.action),
+        Commands::Up(args) => up_command(args.file, args.dry_run),
+        Commands::Down(args) => down_command(args.file, args.timeout),
         Commands::List(_) => list_command(),
-        Commands::Print(args) => print_command(args.name),
+        Commands::Print(args) => print_command(args.name, args.resolved, args.env_files, args.sets),
+        Commands::Migrate(args) => migrate_command(args.names, args.all),
+        Commands::Convert(args) => convert_command(args.name, args.to),
+        Commands::Edit(args) => edit_command(args.name),
         Commands::Rm(args) => remove_command(args.name),
         Commands::Rename(args) => {
             rename_command(args.desc, args.force, args.old_name, args.new_name)
         }
-        Commands::Save(args) => save_command(
-            args.force,
-            args.name,
-            args.desc,
-            args.qemu_bin,
-            args.qemu_args,
-        ),
+        Commands::Save(args) => save_command(args),
         Commands::Completions(args) => completions_command(args.shell),
     }
 }
@@ -1,5 +1,20 @@
-use anyhow::Result;
+use std::process::ExitCode;
 
-fn main() -> Result<()> {
-    vex::run()
+use vex::error::VexError;
+
+fn main() -> ExitCode {
+    match vex::run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => match err.downcast_ref::<VexError>() {
+            // Pass exit statuses such as QEMU's own through unchanged
+            Some(VexError::Exit { code, message }) => {
+                eprintln!("{}", message);
+                ExitCode::from(*code)
+            }
+            _ => {
+                eprintln!("Error: {:?}", err);
+                ExitCode::FAILURE
+            }
+        },
+    }
 }
@@ -0,0 +1,98 @@
+use std::net::TcpListener;
+
+use crate::compose::ComposeFile;
+
+fn compose(json: &str) -> ComposeFile {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_start_order_follows_dependencies() {
+    let compose = compose(
+        r#"{"vms": [
+            {"name": "client", "config": "c", "depends_on": ["server"]},
+            {"name": "server", "config": "s", "depends_on": ["db"]},
+            {"name": "db", "config": "d"}
+        ]}"#,
+    );
+    let order: Vec<&str> = compose
+        .start_order()
+        .unwrap()
+        .iter()
+        .map(|vm| vm.name.as_str())
+        .collect();
+    assert_eq!(order, ["db", "server", "client"]);
+}
+
+#[test]
+fn test_start_order_detects_cycles() {
+    let compose = compose(
+        r#"{"vms": [
+            {"name": "a", "config": "a", "depends_on": ["b"]},
+            {"name": "b", "config": "b", "depends_on": ["a"]}
+        ]}"#,
+    );
+    let err = compose.start_order().unwrap_err().to_string();
+    assert!(err.contains("Dependency cycle between VMs: a, b"));
+}
+
+#[test]
+fn test_socket_network_listener_starts_first() {
+    let compose = compose(
+        r#"{
+            "networks": {"link": {"kind": "socket", "addr": "127.0.0.1:4444"}},
+            "vms": [
+                {"name": "client", "config": "c", "networks": ["link"], "depends_on": ["server"]},
+                {"name": "server", "config": "s", "networks": ["link"]}
+            ]
+        }"#,
+    );
+    let order = compose.start_order().unwrap();
+    let server = compose.network_args("lab", order[0], &order);
+    let client = compose.network_args("lab", order[1], &order);
+    assert_eq!(server[1], "socket,id=vexnet-link,listen=127.0.0.1:4444");
+    assert_eq!(client[1], "socket,id=vexnet-link,connect=127.0.0.1:4444");
+    // Each VM gets its own MAC address
+    assert!(server[3].starts_with("virtio-net-pci,netdev=vexnet-link,mac=52:54:00:"));
+    assert_ne!(server[3], client[3]);
+}
+
+#[test]
+fn test_network_port_collisions_are_reported() {
+    let compose = compose(
+        r#"{
+            "networks": {
+                "a": {"addr": "230.0.0.1:4444"},
+                "b": {"addr": "230.0.0.1:4444"}
+            },
+            "vms": [{"name": "vm", "config": "c", "networks": ["a", "b"]}]
+        }"#,
+    );
+    let err = compose
+        .check_network_ports("lab", |_| Ok(false))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Networks 'a' and 'b' both use 230.0.0.1:4444"));
+}
+
+#[test]
+fn test_network_port_in_use_is_reported() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let compose = compose(&format!(
+        r#"{{
+            "networks": {{"link": {{"kind": "socket", "addr": "{}"}}}},
+            "vms": [{{"name": "vm", "config": "c", "networks": ["link"]}}]
+        }}"#,
+        addr
+    ));
+    let err = compose
+        .check_network_ports("lab", |_| Ok(false))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(&format!("Port {} of network 'link'", addr.port())));
+    assert!(err.contains("already in use"));
+
+    // A running member holds the port itself
+    compose.check_network_ports("lab", |_| Ok(true)).unwrap();
+}
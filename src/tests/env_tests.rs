@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+use crate::config::{EnvFileVar, merge_variables, parse_env_file};
+
+#[test]
+fn test_parse_env_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("dev.env");
+    std::fs::write(
+        &path,
+        "# kernel under test\nKERNEL=/build/Image\n\nexport MEM = 2G\nCMDLINE=\"console=ttyS0 quiet\"\nEMPTY=\n",
+    )
+    .unwrap();
+
+    let vars = parse_env_file(&path).unwrap();
+    assert_eq!(
+        vars,
+        [
+            ("KERNEL".to_string(), "/build/Image".to_string()),
+            ("MEM".to_string(), "2G".to_string()),
+            ("CMDLINE".to_string(), "console=ttyS0 quiet".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_env_file_comments_and_quotes() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("dev.env");
+    std::fs::write(
+        &path,
+        concat!(
+            "KERNEL=/build/Image # built by make\n",
+            "TAG=v1#rc\n",
+            "CMDLINE=\"console=ttyS0 # not a comment\" # but this is\n",
+            "LITERAL='$HOME \\n'\n",
+            "ESCAPED=\"say \\\"hi\\\" \\\\ bye\"\n",
+        ),
+    )
+    .unwrap();
+
+    let vars = parse_env_file(&path).unwrap();
+    assert_eq!(
+        vars,
+        [
+            ("KERNEL".to_string(), "/build/Image".to_string()),
+            ("TAG".to_string(), "v1#rc".to_string()),
+            (
+                "CMDLINE".to_string(),
+                "console=ttyS0 # not a comment".to_string()
+            ),
+            ("LITERAL".to_string(), "$HOME \\n".to_string()),
+            ("ESCAPED".to_string(), "say \"hi\" \\ bye".to_string()),
+        ]
+    );
+
+    for (line, error) in [
+        ("A=\"open", "missing closing \" in \"open"),
+        ("A='x' y", "unexpected text after closing quote in 'x' y"),
+    ] {
+        std::fs::write(&path, line).unwrap();
+        let err = parse_env_file(&path).unwrap_err().to_string();
+        assert!(err.ends_with(&format!("dev.env:1: {}", error)), "{}", err);
+    }
+}
+
+#[test]
+fn test_parse_env_file_reports_line() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("bad.env");
+    std::fs::write(&path, "A=1\njust words\n").unwrap();
+
+    let err = parse_env_file(&path).unwrap_err().to_string();
+    assert!(err.ends_with("bad.env:2: expected KEY=VALUE, found 'just words'"));
+}
+
+#[test]
+fn test_merge_variables_precedence() {
+    let file_var = |value: &str| EnvFileVar {
+        value: value.to_string(),
+        source: PathBuf::from("dev.env"),
+    };
+    let file_vars = BTreeMap::from([
+        ("VEX_TEST_FROM_FILE".to_string(), file_var("file")),
+        ("mem".to_string(), file_var("1G")),
+        // Set in the process environment of every test run
+        ("PATH".to_string(), file_var("/nowhere")),
+    ]);
+    let params = BTreeMap::from([("mem".to_string(), "4G".to_string())]);
+
+    let vars = merge_variables(&params, &file_vars);
+    assert_eq!(vars["VEX_TEST_FROM_FILE"], "file");
+    assert_eq!(vars["mem"], "4G");
+    assert!(!vars.contains_key("PATH"));
+}
@@ -1,10 +1,15 @@
-use crate::commands::exec::substitute_params;
+use crate::commands::exec::{substitute_params, substitute_value};
+use std::collections::BTreeMap;
+
+fn no_params() -> BTreeMap<String, String> {
+    BTreeMap::new()
+}
 
 #[test]
 fn test_substitute_single_var() {
     unsafe { std::env::set_var("TEST_VAR", "value") };
     let args = vec!["${TEST_VAR}".to_string()];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["value"]);
 }
 
@@ -15,7 +20,7 @@ fn test_substitute_multiple_vars() {
         std::env::set_var("VAR2", "world");
     }
     let args = vec!["${VAR1} ${VAR2}".to_string()];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["hello world"]);
 }
 
@@ -23,7 +28,7 @@ fn test_substitute_multiple_vars() {
 fn test_substitute_undefined_var() {
     unsafe { std::env::remove_var("UNDEFINED_VAR") };
     let args = vec!["${UNDEFINED_VAR}".to_string()];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["${UNDEFINED_VAR}"]);
 }
 
@@ -34,21 +39,21 @@ fn test_substitute_mixed() {
         std::env::remove_var("UNDEFINED");
     }
     let args = vec!["prefix_${DEFINED}_${UNDEFINED}_suffix".to_string()];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["prefix_yes_${UNDEFINED}_suffix"]);
 }
 
 #[test]
 fn test_substitute_no_vars() {
     let args = vec!["no_vars_here".to_string()];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["no_vars_here"]);
 }
 
 #[test]
 fn test_substitute_empty_args() {
     let args: Vec<String> = vec![];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, Vec::<String>::new());
 }
 
@@ -60,7 +65,7 @@ fn test_substitute_multiple_args() {
         "-m".to_string(),
         "2048".to_string(),
     ];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["/usr/bin/qemu", "-m", "2048"]);
 }
 
@@ -68,6 +73,116 @@ fn test_substitute_multiple_args() {
 fn test_substitute_nested_braces() {
     unsafe { std::env::set_var("OUTER", "value") };
     let args = vec!["${OUTER}".to_string()];
-    let result = substitute_params(&args);
+    let result = substitute_params(&args, &no_params()).unwrap();
     assert_eq!(result, vec!["value"]);
 }
+
+#[test]
+fn test_substitute_default_value() {
+    unsafe {
+        std::env::remove_var("VEX_TEST_UNSET_MEM");
+        std::env::set_var("VEX_TEST_EMPTY_MEM", "");
+        std::env::set_var("VEX_TEST_SET_MEM", "8G");
+    }
+    assert_eq!(
+        substitute_value("${VEX_TEST_UNSET_MEM:-2G}", &no_params()).unwrap(),
+        "2G"
+    );
+    assert_eq!(
+        substitute_value("${VEX_TEST_EMPTY_MEM:-2G}", &no_params()).unwrap(),
+        "2G"
+    );
+    assert_eq!(
+        substitute_value("${VEX_TEST_SET_MEM:-2G}", &no_params()).unwrap(),
+        "8G"
+    );
+    assert_eq!(
+        substitute_value("${VEX_TEST_UNSET_MEM:-}x", &no_params()).unwrap(),
+        "x"
+    );
+}
+
+#[test]
+fn test_substitute_required_value() {
+    unsafe {
+        std::env::remove_var("VEX_TEST_KERNEL");
+        std::env::set_var("VEX_TEST_ROOTFS", "rootfs.img");
+    }
+    let err = substitute_value("${VEX_TEST_KERNEL:?path to bzImage}", &no_params())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("VEX_TEST_KERNEL"));
+    assert!(err.contains("path to bzImage"));
+
+    let err = substitute_value("${VEX_TEST_KERNEL:?}", &no_params())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("not set"));
+
+    assert_eq!(
+        substitute_value("${VEX_TEST_ROOTFS:?rootfs needed}", &no_params()).unwrap(),
+        "rootfs.img"
+    );
+}
+
+#[test]
+fn test_substitute_escaped_dollar() {
+    unsafe { std::env::set_var("VEX_TEST_ESCAPED", "no") };
+    assert_eq!(
+        substitute_value("$${VEX_TEST_ESCAPED}/${VEX_TEST_ESCAPED}", &no_params()).unwrap(),
+        "${VEX_TEST_ESCAPED}/no"
+    );
+    assert_eq!(
+        substitute_value("$${VEX_TEST_ESCAPED:-x}", &no_params()).unwrap(),
+        "${VEX_TEST_ESCAPED:-x}"
+    );
+}
+
+#[test]
+fn test_substitute_params_before_env() {
+    unsafe { std::env::set_var("vex_test_mem", "1G") };
+    let params = BTreeMap::from([("vex_test_mem".to_string(), "4G".to_string())]);
+    assert_eq!(substitute_value("${vex_test_mem}", &params).unwrap(), "4G");
+    assert_eq!(
+        substitute_value("${vex_test_mem}", &no_params()).unwrap(),
+        "1G"
+    );
+}
+
+#[test]
+fn test_gdb_port_parse() {
+    use crate::config::GdbPort;
+
+    assert_eq!("auto".parse::<GdbPort>(), Ok(GdbPort::Auto));
+    assert_eq!("4321".parse::<GdbPort>(), Ok(GdbPort::Fixed(4321)));
+    assert!("0".parse::<GdbPort>().is_err());
+    assert!("70000".parse::<GdbPort>().is_err());
+    assert!("port".parse::<GdbPort>().is_err());
+}
+
+#[test]
+fn test_parse_duration() {
+    use crate::utils::time::parse_duration;
+    use std::time::Duration;
+
+    assert_eq!(parse_duration("120"), Ok(Duration::from_secs(120)));
+    assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+    assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+    assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+    assert!(parse_duration("5 minutes").is_err());
+    assert!(parse_duration("m").is_err());
+    assert_eq!(
+        parse_duration("18446744073709551615h"),
+        Err("duration '18446744073709551615h' is too large".to_string())
+    );
+    assert_eq!(
+        parse_duration("307445734561825861m"),
+        Err("duration '307445734561825861m' is too large".to_string())
+    );
+    assert_eq!(
+        parse_duration("99999999999999999999s"),
+        Err("duration '99999999999999999999s' is too large".to_string())
+    );
+    assert!(parse_duration("18446744073709551615s").is_ok());
+}
@@ -0,0 +1,50 @@
+use crate::utils::expect::{ExpectMatcher, ExpectOutcome};
+
+fn matcher(expect: &[&str], fail_on: &[&str]) -> ExpectMatcher {
+    let to_vec = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    ExpectMatcher::new(&to_vec(expect), &to_vec(fail_on)).unwrap()
+}
+
+#[test]
+fn test_expect_matches_prompt_without_newline() {
+    let mut m = matcher(&["login:"], &[]);
+    assert_eq!(m.feed("Welcome\nbuildroot lo"), None);
+    assert_eq!(
+        m.feed("gin: "),
+        Some(ExpectOutcome::Matched("login:".to_string()))
+    );
+}
+
+#[test]
+fn test_expect_fail_on() {
+    let mut m = matcher(&["login:"], &["Kernel panic"]);
+    assert_eq!(
+        m.feed("[    1.0] Kernel panic - not syncing\n"),
+        Some(ExpectOutcome::Failed("Kernel panic".to_string()))
+    );
+}
+
+#[test]
+fn test_expect_earliest_match_wins() {
+    let mut m = matcher(&["login:"], &["panic"]);
+    assert_eq!(
+        m.feed("login: \npanic\n"),
+        Some(ExpectOutcome::Matched("login:".to_string()))
+    );
+}
+
+#[test]
+fn test_expect_does_not_rematch_old_lines() {
+    let mut m = matcher(&["ready$"], &[]);
+    assert_eq!(m.feed("not ready yet\n"), None);
+    assert_eq!(m.feed("still booting\n"), None);
+    assert_eq!(
+        m.feed("ready"),
+        Some(ExpectOutcome::Matched("ready$".to_string()))
+    );
+}
+
+#[test]
+fn test_expect_invalid_pattern() {
+    assert!(ExpectMatcher::new(&["(".to_string()], &[]).is_err());
+}
@@ -1,5 +1,63 @@
+mod compose_tests;
+mod env_tests;
 mod exec_tests;
+mod expect_tests;
+mod net_tests;
+mod params_tests;
+mod process_tests;
+mod qemu_args_tests;
+mod qemu_options_tests;
+mod qemu_tests;
+mod qmp_tests;
+mod shell_tests;
+mod snapshot_tests;
+mod storage_tests;
+pub mod test_compose;
+pub mod test_edit;
+pub mod test_exec;
+pub mod test_exit_status;
+pub mod test_formats;
+pub mod test_history;
+pub mod test_hooks;
+pub mod test_inherit;
+pub mod test_instances;
 pub mod test_list;
+pub mod test_logs;
+pub mod test_migrate;
 pub mod test_remove;
 pub mod test_rename;
 pub mod test_save;
+pub mod test_sidecars;
+pub mod test_snapshot;
+pub mod test_ssh;
+
+/// A dry-run command line without its trailing per-run QMP socket, which embeds the vex pid
+pub fn without_qmp_socket(command: &str) -> &str {
+    let (argv, socket) = command
+        .rsplit_once(" -qmp unix:")
+        .unwrap_or_else(|| panic!("no QMP socket in dry-run output: {}", command));
+    assert!(
+        socket.ends_with(".qmp,server=on,wait=off"),
+        "unexpected QMP socket: {}",
+        socket
+    );
+    argv
+}
+
+/// Save a configuration that runs a shell script in place of QEMU, replacing any existing one.
+///
+/// `sh -c` ignores the QMP arguments vex appends (they become $0, $1).
+pub fn save_shell_config(
+    vex_bin: &escargot::CargoRun,
+    config_dir: &std::path::Path,
+    name: &str,
+    script: &str,
+) {
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", config_dir)
+        .args(["save", "-f", name, "sh", "-c", script])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
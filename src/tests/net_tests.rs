@@ -0,0 +1,21 @@
+use std::net::{TcpListener, UdpSocket};
+
+use crate::config::Protocol;
+use crate::utils::net::{is_addr_port_free, is_port_free, is_proto_port_free};
+
+#[test]
+fn test_port_taken_on_other_interface_is_not_free() {
+    // QEMU binds forwards on all interfaces, so a port held on any address is in use
+    let busy = TcpListener::bind(("127.0.0.2", 0)).unwrap();
+    let port = busy.local_addr().unwrap().port();
+    assert!(!is_port_free(port));
+    assert!(!is_proto_port_free(Protocol::Tcp, port));
+    assert!(!is_addr_port_free(Protocol::Tcp, "", port));
+    // A forward bound to another address doesn't clash
+    assert!(is_addr_port_free(Protocol::Tcp, "127.0.0.3", port));
+
+    let busy = UdpSocket::bind(("127.0.0.2", 0)).unwrap();
+    let port = busy.local_addr().unwrap().port();
+    assert!(!is_proto_port_free(Protocol::Udp, port));
+    assert!(is_addr_port_free(Protocol::Udp, "127.0.0.3", port));
+}
@@ -0,0 +1,124 @@
+use crate::config::{
+    ParamDecl, ParamSpec, ParamType, QemuConfig, SCHEMA_VERSION, check_defaults, resolve_params,
+};
+use std::collections::BTreeMap;
+
+fn config_with_params(params: &[(&str, ParamType, Option<&str>)]) -> QemuConfig {
+    QemuConfig {
+        schema_version: SCHEMA_VERSION,
+        qemu_bin: "qemu-system-x86_64".to_string(),
+        args: vec![],
+        desc: None,
+        qemu_version: None,
+        extends: None,
+        remove_args: Vec::new(),
+        replace_args: Vec::new(),
+        gdb_port: None,
+        forwards: vec![],
+        ssh: None,
+        cwd: None,
+        sidecars: vec![],
+        pre_exec: vec![],
+        post_exec: vec![],
+        params: params
+            .iter()
+            .map(|(name, kind, default)| {
+                let spec = ParamSpec {
+                    kind: *kind,
+                    default: default.map(str::to_string),
+                    help: None,
+                };
+                (name.to_string(), spec)
+            })
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
+
+fn set(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+#[test]
+fn test_param_decl_parse() {
+    let decl: ParamDecl = "mem:size=4G".parse().unwrap();
+    assert_eq!(decl.name, "mem");
+    assert_eq!(decl.spec.kind, ParamType::Size);
+    assert_eq!(decl.spec.default.as_deref(), Some("4G"));
+
+    let decl: ParamDecl = "kernel:path".parse().unwrap();
+    assert_eq!(decl.spec.kind, ParamType::Path);
+    assert_eq!(decl.spec.default, None);
+
+    let decl: ParamDecl = "label=a:b".parse().unwrap();
+    assert_eq!(decl.spec.kind, ParamType::String);
+    assert_eq!(decl.spec.default.as_deref(), Some("a:b"));
+
+    let decl: ParamDecl = "mem:size=2G#Guest memory".parse().unwrap();
+    assert_eq!(decl.spec.default.as_deref(), Some("2G"));
+    assert_eq!(decl.spec.help.as_deref(), Some("Guest memory"));
+
+    let decl: ParamDecl = "kernel:path#Kernel image to boot".parse().unwrap();
+    assert_eq!(decl.spec.default, None);
+    assert_eq!(decl.spec.help.as_deref(), Some("Kernel image to boot"));
+
+    assert!("mem:bytes".parse::<ParamDecl>().is_err());
+    assert!("bad-name".parse::<ParamDecl>().is_err());
+}
+
+#[test]
+fn test_resolve_params_defaults_and_sets() {
+    let config = config_with_params(&[
+        ("mem", ParamType::Size, Some("2G")),
+        ("cpus", ParamType::Int, Some("2")),
+    ]);
+    let values = resolve_params(&config, &[set("mem", "8G")]).unwrap();
+    assert_eq!(values["mem"], "8G");
+    assert_eq!(values["cpus"], "2");
+}
+
+#[test]
+fn test_resolve_params_type_errors() {
+    let config = config_with_params(&[
+        ("mem", ParamType::Size, Some("2G")),
+        ("cpus", ParamType::Int, Some("2")),
+        ("kernel", ParamType::Path, Some("/")),
+    ]);
+    assert!(resolve_params(&config, &[set("cpus", "four")]).is_err());
+    assert!(resolve_params(&config, &[set("mem", "lots")]).is_err());
+    assert!(resolve_params(&config, &[set("kernel", "/no/such/bzImage")]).is_err());
+    assert!(resolve_params(&config, &[set("mem", "512M"), set("cpus", "4")]).is_ok());
+}
+
+#[test]
+fn test_resolve_params_missing_and_unknown() {
+    let config = config_with_params(&[("kernel", ParamType::String, None)]);
+    let err = resolve_params(&config, &[]).unwrap_err().to_string();
+    assert!(err.contains("--set kernel="));
+
+    let err = resolve_params(&config, &[set("kernel", "x"), set("initrd", "y")])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Unknown parameter 'initrd'"));
+}
+
+#[test]
+fn test_check_defaults() {
+    let config = config_with_params(&[
+        ("mem", ParamType::Size, Some("4G")),
+        ("cpus", ParamType::Int, Some("2")),
+        ("disk", ParamType::Path, None),
+    ]);
+    assert!(check_defaults(&config).is_ok());
+
+    let config = config_with_params(&[("mem", ParamType::Size, Some("banana"))]);
+    let err = format!("{:#}", check_defaults(&config).unwrap_err());
+    assert!(
+        err.contains("'banana' is not a valid size value"),
+        "{}",
+        err
+    );
+
+    // A default path may not exist yet when saving
+    let config = config_with_params(&[("disk", ParamType::Path, Some("/no/such/disk.img"))]);
+    assert!(check_defaults(&config).is_ok());
+}
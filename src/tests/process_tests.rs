@@ -0,0 +1,13 @@
+use crate::utils::process::{is_same_process, process_start_time};
+
+#[test]
+fn test_process_start_time_identifies_process() {
+    let pid = std::process::id();
+    let start = process_start_time(pid).unwrap();
+    assert_eq!(process_start_time(pid), Some(start));
+
+    assert!(is_same_process(pid, Some(start)));
+    assert!(is_same_process(pid, None));
+    // Same pid, different start time: the pid was reused
+    assert!(!is_same_process(pid, Some(start + 1)));
+}
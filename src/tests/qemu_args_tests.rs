@@ -0,0 +1,146 @@
+use crate::utils::qemu_args::{OptionValue, QemuArg, QemuArgs, Suboption, Suboptions};
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_round_trip_is_lossless() {
+    let cases = [
+        argv(&[
+            "-machine",
+            "q35,accel=kvm",
+            "--m",
+            "2G",
+            "-drive",
+            "file=disk,,1.qcow2,if=virtio,readonly",
+            "-append",
+            "console=ttyS0 root=/dev/vda,rw",
+            "-nographic",
+            "image.img",
+        ]),
+        argv(&["-device", "", "-netdev", "user,", "-object", "=x,a==b,,,c"]),
+        argv(&["-cpu", "host,+vmx,-svm", "-S", "-s"]),
+        argv(&[]),
+    ];
+    for case in cases {
+        assert_eq!(QemuArgs::parse(&case).to_argv(), case);
+    }
+}
+
+#[test]
+fn test_parse_groups_options_and_values() {
+    let args = QemuArgs::parse(&argv(&["disk.img", "-m", "2G", "-nographic"]));
+    assert_eq!(args.items.len(), 3);
+    assert_eq!(args.items[0], QemuArg::Positional("disk.img".to_string()));
+    assert_eq!(args.items[1].name(), Some("-m"));
+    assert!(args.items[2].value().is_none());
+}
+
+#[test]
+fn test_suboptions() {
+    let args = QemuArgs::parse(&argv(&[
+        "-drive",
+        "file=a.qcow2,if=virtio",
+        "--drive",
+        "file=b,,c.raw,format=raw",
+        "-device",
+        "virtio-net-pci,netdev=n0",
+    ]));
+
+    let drives: Vec<_> = args
+        .options("-drive")
+        .filter_map(QemuArg::value)
+        .filter_map(OptionValue::suboptions)
+        .filter_map(|subopts| subopts.get("file"))
+        .collect();
+    assert_eq!(drives, ["a.qcow2", "b,c.raw"]);
+
+    let device = args
+        .value("-device")
+        .and_then(OptionValue::suboptions)
+        .unwrap();
+    assert_eq!(device.implied(), Some("virtio-net-pci"));
+    assert_eq!(device.get("netdev"), Some("n0"));
+}
+
+#[test]
+fn test_plain_values_are_not_split() {
+    let args = QemuArgs::parse(&argv(&["-append", "console=ttyS0,115200 quiet"]));
+    assert_eq!(
+        args.value("-append"),
+        Some(&OptionValue::Plain(
+            "console=ttyS0,115200 quiet".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_suboptions_set_and_escape() {
+    let mut subopts = Suboptions::parse("virtio-blk,drive=d0");
+    subopts.set("drive", "d1");
+    subopts.set("serial", "a,b");
+    assert_eq!(subopts.to_string(), "virtio-blk,drive=d1,serial=a,,b");
+    assert_eq!(
+        subopts.entries[2],
+        Suboption {
+            key: Some("serial".to_string()),
+            value: "a,b".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_rebase_paths() {
+    let dir = std::path::Path::new("/work/base");
+    let rebased = |args: &[&str]| {
+        let mut parsed = QemuArgs::parse(&argv(args));
+        for item in &mut parsed.items {
+            item.rebase_paths(dir);
+        }
+        parsed.to_argv()
+    };
+
+    assert_eq!(
+        rebased(&[
+            "-kernel",
+            "./bzImage",
+            "-append",
+            "root=/dev/vda",
+            "disk.img"
+        ]),
+        argv(&[
+            "-kernel",
+            "/work/base/bzImage",
+            "-append",
+            "root=/dev/vda",
+            "/work/base/disk.img"
+        ])
+    );
+    assert_eq!(
+        rebased(&[
+            "-drive",
+            "file=../img/a,,b.qcow2,if=virtio",
+            "-bios",
+            "/usr/share/ovmf.fd"
+        ]),
+        argv(&[
+            "-drive",
+            "file=/work/img/a,,b.qcow2,if=virtio",
+            "-bios",
+            "/usr/share/ovmf.fd"
+        ])
+    );
+    // Node names, protocols and variables are not paths to rebase
+    let untouched = [
+        "-blockdev",
+        "driver=qcow2,file=proto0,node-name=disk0",
+        "-drive",
+        "file=nbd:localhost:10809",
+        "-drive",
+        "file=${DISK}",
+        "-m",
+        "2G",
+    ];
+    assert_eq!(rebased(&untouched), argv(&untouched));
+}
@@ -0,0 +1,70 @@
+use crate::utils::qemu_options::{Token, lookup, tokenize, untokenize};
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_tokenize_uses_option_table() {
+    let args = argv(&["-S", "-append", "-S", "-nographic", "disk.img", "--m", "2G"]);
+    let tokens = tokenize(&args);
+    assert_eq!(
+        tokens,
+        [
+            Token {
+                option: Some("-S"),
+                value: None
+            },
+            Token {
+                option: Some("-append"),
+                value: Some("-S")
+            },
+            Token {
+                option: Some("-nographic"),
+                value: None
+            },
+            Token {
+                option: None,
+                value: Some("disk.img")
+            },
+            Token {
+                option: Some("--m"),
+                value: Some("2G")
+            },
+        ]
+    );
+    assert_eq!(tokens[4].name(), Some("-m"));
+    assert_eq!(untokenize(&tokens), args);
+}
+
+#[test]
+fn test_tokenize_unknown_options() {
+    let args = argv(&["-x-custom", "on", "-y-flag", "-m", "1G"]);
+    let tokens = tokenize(&args);
+    assert_eq!(tokens[0].value, Some("on"));
+    assert_eq!(tokens[1].value, None);
+    assert_eq!(tokens[1].spec(), None);
+    assert_eq!(untokenize(&tokens), args);
+}
+
+#[test]
+fn test_tokenize_missing_value() {
+    let args = argv(&["-nographic", "-kernel"]);
+    let tokens = tokenize(&args);
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1].value, None);
+}
+
+#[test]
+fn test_lookup() {
+    let machine = lookup("-M").unwrap();
+    assert_eq!(machine.name, "-machine");
+    assert!(machine.takes_arg && !machine.repeatable);
+    assert_eq!(lookup("--machine"), Some(machine));
+
+    let device = lookup("-device").unwrap();
+    assert!(device.repeatable);
+    assert!(lookup("-no-hpet").unwrap().deprecated);
+    assert!(!lookup("-s").unwrap().takes_arg);
+    assert_eq!(lookup("-not-an-option"), None);
+}
@@ -0,0 +1,125 @@
+use crate::config::{PortForward, Protocol};
+use crate::utils::qemu::{add_host_forwards, find_host_forward, host_forward_ports, merge_args};
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_merge_replaces_single_valued() {
+    let base = argv(&["-m", "2G", "-smp", "2", "-nographic"]);
+    let merged = merge_args(&base, &argv(&["-m", "8G"]));
+    assert_eq!(merged, argv(&["-m", "8G", "-smp", "2", "-nographic"]));
+}
+
+#[test]
+fn test_merge_appends_repeatable() {
+    let base = argv(&["-drive", "file=a.qcow2", "-device", "virtio-net"]);
+    let merged = merge_args(&base, &argv(&["-device", "virtio-rng-pci"]));
+    assert_eq!(
+        merged,
+        argv(&[
+            "-drive",
+            "file=a.qcow2",
+            "-device",
+            "virtio-net",
+            "-device",
+            "virtio-rng-pci"
+        ])
+    );
+}
+
+#[test]
+fn test_merge_machine_alias() {
+    let base = argv(&["-M", "virt", "-kernel", "Image"]);
+    let merged = merge_args(&base, &argv(&["-machine", "virt,gic-version=3"]));
+    assert_eq!(
+        merged,
+        argv(&["-machine", "virt,gic-version=3", "-kernel", "Image"])
+    );
+}
+
+#[test]
+fn test_merge_adds_missing_single_valued() {
+    let base = argv(&["-nographic"]);
+    let merged = merge_args(&base, &argv(&["-append", "console=ttyS0 quiet"]));
+    assert_eq!(
+        merged,
+        argv(&["-nographic", "-append", "console=ttyS0 quiet"])
+    );
+}
+
+#[test]
+fn test_add_host_forwards_to_user_netdev() {
+    let mut args = argv(&["-netdev", "user,id=n0", "-device", "e1000,netdev=n0"]);
+    add_host_forwards(&mut args, &argv(&["tcp::2222-:22"]));
+    assert_eq!(args[1], "user,id=n0,hostfwd=tcp::2222-:22");
+}
+
+#[test]
+fn test_add_host_forwards_without_user_network() {
+    let mut args = argv(&["-netdev", "tap,id=t0"]);
+    add_host_forwards(&mut args, &argv(&["tcp::8080-:80", "udp::5353-:53"]));
+    assert_eq!(
+        args,
+        argv(&[
+            "-netdev",
+            "tap,id=t0",
+            "-nic",
+            "user,hostfwd=tcp::8080-:80,hostfwd=udp::5353-:53"
+        ])
+    );
+}
+
+#[test]
+fn test_port_forward_parse() {
+    let forward: PortForward = "2222:22".parse().unwrap();
+    assert_eq!(forward.proto, Protocol::Tcp);
+    assert_eq!(forward.hostfwd().as_deref(), Some("tcp::2222-:22"));
+
+    let forward: PortForward = "udp:auto:53".parse().unwrap();
+    assert_eq!(forward.host, None);
+    assert_eq!(forward.hostfwd(), None);
+    assert_eq!(forward.to_string(), "udp:auto:53");
+
+    assert!("sctp:1:2".parse::<PortForward>().is_err());
+    assert!("0:22".parse::<PortForward>().is_err());
+    assert!("22".parse::<PortForward>().is_err());
+}
+
+#[test]
+fn test_find_host_forward() {
+    let args = argv(&[
+        "-netdev",
+        "user,id=n0,hostfwd=udp::5353-:22,hostfwd=tcp:0.0.0.0:2222-:22",
+    ]);
+    assert_eq!(
+        find_host_forward(&args, 22),
+        Some(("0.0.0.0".to_string(), 2222))
+    );
+
+    let args = argv(&["-nic", "user,hostfwd=::8022-10.0.2.15:22"]);
+    assert_eq!(
+        find_host_forward(&args, 22),
+        Some(("127.0.0.1".to_string(), 8022))
+    );
+    assert_eq!(find_host_forward(&args, 80), None);
+}
+
+#[test]
+fn test_host_forward_ports() {
+    let args = argv(&[
+        "-nic",
+        "user,hostfwd=tcp::2222-:22,hostfwd=udp:127.0.0.1:5353-:53",
+        "-netdev",
+        "user,id=n0,hostfwd=:10.0.0.5:8080-:80",
+    ]);
+    assert_eq!(
+        host_forward_ports(&args),
+        vec![
+            (Protocol::Tcp, String::new(), 2222),
+            (Protocol::Udp, "127.0.0.1".to_string(), 5353),
+            (Protocol::Tcp, "10.0.0.5".to_string(), 8080),
+        ]
+    );
+}
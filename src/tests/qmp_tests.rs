@@ -0,0 +1,78 @@
+use crate::utils::qmp::QmpClient;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixListener;
+use std::thread;
+use tempfile::TempDir;
+
+/// Serve one QMP connection, answering each command with the given handler
+fn fake_qmp_server(
+    listener: UnixListener,
+    handler: fn(&Value) -> Vec<Value>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let greeting = json!({"QMP": {"version": {"qemu": {"major": 8, "minor": 2, "micro": 0}}, "capabilities": []}});
+        writeln!(writer, "{}", greeting).unwrap();
+
+        for line in BufReader::new(stream).lines() {
+            let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+            let replies = if request["execute"] == "qmp_capabilities" {
+                vec![json!({"return": {}, "id": request["id"]})]
+            } else {
+                handler(&request)
+            };
+            for reply in replies {
+                writeln!(writer, "{}", reply).unwrap();
+            }
+        }
+    })
+}
+
+#[test]
+fn test_qmp_execute_returns_value() {
+    let temp_dir = TempDir::new().unwrap();
+    let socket = temp_dir.path().join("qmp.sock");
+    let listener = UnixListener::bind(&socket).unwrap();
+    let server = fake_qmp_server(listener, |request| {
+        vec![
+            json!({"event": "RESUME", "timestamp": {"seconds": 0, "microseconds": 0}}),
+            json!({"return": {"status": "running", "running": true}, "id": request["id"]}),
+        ]
+    });
+
+    let mut client = QmpClient::connect(&socket).unwrap();
+    assert_eq!(client.greeting()["QMP"]["version"]["qemu"]["major"], 8);
+
+    let reply = client.execute("query-status", None).unwrap();
+    assert_eq!(reply["status"], "running");
+
+    let events = client.take_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["event"], "RESUME");
+
+    drop(client);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_qmp_execute_reports_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let socket = temp_dir.path().join("qmp.sock");
+    let listener = UnixListener::bind(&socket).unwrap();
+    let server = fake_qmp_server(listener, |request| {
+        vec![json!({
+            "error": {"class": "CommandNotFound", "desc": "The command bogus has not been found"},
+            "id": request["id"]
+        })]
+    });
+
+    let mut client = QmpClient::connect(&socket).unwrap();
+    let err = client.execute("bogus", None).unwrap_err().to_string();
+    assert!(err.contains("has not been found"));
+    assert!(err.contains("CommandNotFound"));
+
+    drop(client);
+    server.join().unwrap();
+}
@@ -0,0 +1,47 @@
+use crate::utils::shell::{shell_join, shell_quote, shell_split};
+
+#[test]
+fn test_shell_quote_safe_word() {
+    assert_eq!(shell_quote("-m"), "-m");
+    assert_eq!(
+        shell_quote("file=/vm/disk.qcow2,if=virtio"),
+        "file=/vm/disk.qcow2,if=virtio"
+    );
+}
+
+#[test]
+fn test_shell_quote_spaces_and_quotes() {
+    assert_eq!(
+        shell_quote("console=ttyS0 root=/dev/vda"),
+        "'console=ttyS0 root=/dev/vda'"
+    );
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    assert_eq!(shell_quote(""), "''");
+    assert_eq!(shell_quote("${HOME}"), "'${HOME}'");
+}
+
+#[test]
+fn test_shell_join() {
+    let argv = ["qemu-system-x86_64", "-append", "quiet splash"];
+    assert_eq!(
+        shell_join(argv),
+        "qemu-system-x86_64 -append 'quiet splash'"
+    );
+}
+
+#[test]
+fn test_shell_split() {
+    assert_eq!(
+        shell_split(r#"virtiofsd --socket-path=${socket} --shared-dir "/my src" 'a b'\ c"#)
+            .unwrap(),
+        [
+            "virtiofsd",
+            "--socket-path=${socket}",
+            "--shared-dir",
+            "/my src",
+            "a b c"
+        ]
+    );
+    assert_eq!(shell_split(r#"echo "" x"#).unwrap(), ["echo", "", "x"]);
+    assert!(shell_split("echo 'open").is_err());
+}
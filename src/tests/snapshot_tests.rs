@@ -0,0 +1,94 @@
+use crate::utils::snapshot::qcow2_drives;
+use std::path::PathBuf;
+
+#[test]
+fn test_qcow2_drives_from_drive_options() {
+    let args: Vec<String> = [
+        "-m",
+        "2G",
+        "-drive",
+        "file=/vm/root.qcow2,if=virtio",
+        "-drive",
+        "file=/vm/data.img,format=qcow2",
+        "-drive",
+        "file=/vm/raw.img,format=raw",
+        "-hdb",
+        "/vm/extra.qcow2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    assert_eq!(
+        qcow2_drives(&args),
+        vec![
+            PathBuf::from("/vm/root.qcow2"),
+            PathBuf::from("/vm/data.img"),
+            PathBuf::from("/vm/extra.qcow2"),
+        ]
+    );
+}
+
+#[test]
+fn test_qcow2_drives_escaped_comma() {
+    let args = vec![
+        "-drive".to_string(),
+        "file=/vm/a,,b.qcow2,if=none".to_string(),
+    ];
+    assert_eq!(qcow2_drives(&args), vec![PathBuf::from("/vm/a,b.qcow2")]);
+}
+
+#[test]
+fn test_qcow2_drives_none() {
+    let args = vec!["-kernel".to_string(), "bzImage".to_string()];
+    assert!(qcow2_drives(&args).is_empty());
+}
+
+#[test]
+fn test_qcow2_drives_from_blockdev() {
+    let args: Vec<String> = [
+        "-blockdev",
+        "driver=file,filename=/vm/root.qcow2,node-name=proto0",
+        "-blockdev",
+        "driver=qcow2,file=proto0,node-name=disk0",
+        "-blockdev",
+        "driver=qcow2,file.driver=file,file.filename=/vm/data.qcow2,node-name=disk1",
+        "-blockdev",
+        "driver=raw,file.driver=file,file.filename=/vm/raw.img,node-name=disk2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    assert_eq!(
+        qcow2_drives(&args),
+        vec![
+            PathBuf::from("/vm/root.qcow2"),
+            PathBuf::from("/vm/data.qcow2"),
+        ]
+    );
+}
+
+#[test]
+fn test_qcow2_drives_uses_option_table() {
+    // `--drive` is the same option, and `-append` swallows its value even when it looks like one
+    let args: Vec<String> = [
+        "--drive",
+        "file=/vm/root.qcow2",
+        "-append",
+        "-hda",
+        "-hdb",
+        "/vm/extra.qcow2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    assert_eq!(
+        qcow2_drives(&args),
+        vec![
+            PathBuf::from("/vm/root.qcow2"),
+            PathBuf::from("/vm/extra.qcow2"),
+        ]
+    );
+}
@@ -0,0 +1,58 @@
+use tempfile::TempDir;
+
+use crate::config::{GdbPort, SCHEMA_VERSION, migrate_config_file, read_config};
+use crate::error::VexError;
+
+const V0_CONFIG: &str = include_str!("fixtures/v0-config.json");
+
+#[test]
+fn test_migrate_v0_config_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("old-vm.json");
+    std::fs::write(&path, V0_CONFIG).unwrap();
+
+    let (version, backup) = migrate_config_file("old-vm", &path).unwrap().unwrap();
+    assert_eq!(version, 0);
+    assert_eq!(backup, temp_dir.path().join("old-vm.json.v0.bak"));
+    assert_eq!(std::fs::read_to_string(&backup).unwrap(), V0_CONFIG);
+
+    let config = read_config("old-vm", &path).unwrap();
+    assert_eq!(config.schema_version, SCHEMA_VERSION);
+    assert_eq!(config.qemu_bin, "qemu-system-x86_64");
+    assert_eq!(config.args, ["-m", "2G", "-nographic"]);
+    assert_eq!(config.desc.as_deref(), Some("saved before schema versions"));
+    assert_eq!(config.gdb_port, Some(GdbPort::Fixed(1234)));
+
+    // The string port is stored as a number from now on
+    let migrated: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(migrated["schema_version"], 1);
+    assert_eq!(migrated["gdb_port"], 1234);
+
+    // Migrating again leaves the file alone
+    assert!(migrate_config_file("old-vm", &path).unwrap().is_none());
+}
+
+#[test]
+fn test_migrate_refuses_newer_schema() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("future-vm.json");
+    let future = r#"{"schema_version": 99, "qemu_bin": "echo", "args": []}"#;
+    std::fs::write(&path, future).unwrap();
+
+    let err = migrate_config_file("future-vm", &path).unwrap_err();
+    match err.downcast_ref::<VexError>() {
+        Some(VexError::UnsupportedSchemaVersion {
+            name,
+            version,
+            supported,
+        }) => {
+            assert_eq!(name, "future-vm");
+            assert_eq!(*version, 99);
+            assert_eq!(*supported, SCHEMA_VERSION);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), future);
+    assert!(!temp_dir.path().join("future-vm.json.v99.bak").exists());
+}
@@ -0,0 +1,237 @@
+use escargot::CargoBuild;
+use std::fs;
+use tempfile::TempDir;
+
+const COMPOSE: &str = r#"{
+    "name": "lab",
+    "networks": {"lan": {}},
+    "vms": [
+        {
+            "name": "client",
+            "inline": {"qemu_bin": "sh", "args": ["-c", "exec sleep 30"]},
+            "networks": ["lan"],
+            "depends_on": ["server"]
+        },
+        {"name": "server", "config": "fw-server", "networks": ["lan"]}
+    ]
+}"#;
+
+#[test]
+fn test_compose_up_down() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let compose_file = temp_dir.path().join("vex-compose.json");
+    fs::write(&compose_file, COMPOSE).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "fw-server", "sh", "-c", "exec sleep 30"])
+        .output()
+        .unwrap();
+
+    // Dry run shows the VMs in dependency order on a shared multicast network
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .current_dir(temp_dir.path())
+        .args(["up", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let server = stdout.find("# server").unwrap();
+    let client = stdout.find("# client").unwrap();
+    assert!(server < client);
+    let re = regex::Regex::new(r"socket,id=vexnet-lan,mcast=230\.0\.0\.1:(\d+)").unwrap();
+    let ports: Vec<&str> = re
+        .captures_iter(&stdout)
+        .map(|caps| caps.get(1).unwrap().as_str())
+        .collect();
+    assert_eq!(ports.len(), 2);
+    assert_eq!(ports[0], ports[1]);
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["up", "-f"])
+        .arg(&compose_file)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .arg("ps")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("lab-server"));
+    assert!(stdout.contains("lab-client"));
+
+    // Running VMs are not started twice
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["up", "-f"])
+        .arg(&compose_file)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("VM 'server' is already running"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["down", "-f"])
+        .arg(&compose_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .arg("ps")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    assert!(stdout.contains("no running instances"));
+}
+
+#[test]
+fn test_compose_rejects_unknown_network() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let compose_file = temp_dir.path().join("broken.json");
+    fs::write(
+        &compose_file,
+        r#"{"vms": [{"name": "a", "config": "a", "networks": ["nowhere"]}]}"#,
+    )
+    .unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["up", "-f"])
+        .arg(&compose_file)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("VM 'a' uses unknown network 'nowhere'"));
+}
+
+#[test]
+fn test_compose_env_files_cwd_and_validation() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let project_dir = temp_dir.path().join("project");
+    fs::create_dir_all(&project_dir).unwrap();
+    let compose_file = project_dir.join("vex-compose.json");
+    fs::write(
+        &compose_file,
+        r#"{
+            "name": "env",
+            "vms": [
+                {"name": "server", "config": "env-server"},
+                {"name": "client", "inline": {"qemu_bin": "sh", "args": ["-c", "pwd > client.cwd; exec sleep 30"]}}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .current_dir(temp_dir.path())
+            .args(args)
+            .arg("-f")
+            .arg(&compose_file)
+            .output()
+            .unwrap()
+    };
+
+    // The saved configuration's own env file applies under its compose instance name
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "env-server", "echo", "-name", "${GUEST}"])
+        .output()
+        .unwrap();
+    fs::write(config_dir.join("env-server.env"), "GUEST=from-env-file\n").unwrap();
+    let output = vex(&["up", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("echo -name from-env-file"));
+
+    // Inline VMs run from the compose file's directory
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "-f", "env-server", "sh", "-c", "exec sleep 30"])
+        .output()
+        .unwrap();
+    let output = vex(&["up"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let cwd_file = project_dir.join("client.cwd");
+    for _ in 0..50 {
+        if cwd_file.exists() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(
+        fs::read_to_string(&cwd_file).unwrap().trim(),
+        project_dir.canonicalize().unwrap().display().to_string()
+    );
+    assert!(vex(&["down"]).status.success());
+
+    // Inline VMs are validated before anything is started
+    fs::write(
+        &compose_file,
+        r#"{"vms": [{"name": "broken", "inline": {"args": ["-m", "1G"]}}]}"#,
+    )
+    .unwrap();
+    let output = vex(&["up"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid configuration for VM 'broken'"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("QEMU binary path cannot be empty"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,184 @@
+use escargot::CargoBuild;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+#[test]
+fn test_edit_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |editor: &str, args: &[&str]| {
+        let mut command = vex_bin.command();
+        command
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env_remove("VISUAL")
+            .env("EDITOR", editor)
+            .args(args);
+        command
+    };
+
+    let output = vex("true", &["save", "board", "echo", "-m", "1G"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let config_path = config_dir.join("board.json");
+    let original = std::fs::read_to_string(&config_path).unwrap();
+
+    // An editor that doesn't change anything leaves the file alone
+    let output = vex("true", &["edit", "board"]).output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No changes made"));
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), original);
+
+    // The editor gets a private copy with a random name
+    let seen = temp_dir.path().join("seen");
+    let editor = format!("sh -c 'stat -c \"%a %n\" \"$0\" > {}'", seen.display());
+    let output = vex(&editor, &["edit", "board"]).output().unwrap();
+    assert!(output.status.success());
+    let seen = std::fs::read_to_string(&seen).unwrap();
+    let (mode, path) = seen.trim().split_once(' ').unwrap();
+    assert_eq!(mode, "600");
+    let file_name = std::path::Path::new(path)
+        .file_name()
+        .unwrap()
+        .to_string_lossy();
+    assert!(file_name.starts_with("vex-edit-") && file_name.ends_with(".json"));
+    assert!(!std::path::Path::new(path).exists());
+
+    // A valid edit is saved; without input the trial run defaults to no
+    let output = vex("sed -i s/1G/2G/", &["edit", "board"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Configuration 'board' updated"));
+    let edited = std::fs::read_to_string(&config_path).unwrap();
+    assert!(edited.contains("2G"));
+
+    // An invalid edit isn't saved when reopening the editor is declined
+    let output = vex("sed -i s/qemu_bin/qemu_binary/", &["edit", "board"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("QEMU binary path cannot be empty"),
+        "{}",
+        stderr
+    );
+    assert!(stdout.contains("Reopen the editor"));
+    assert!(stdout.contains("Configuration 'board' left unchanged"));
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), edited);
+
+    // A failing editor discards the edit
+    let output = vex("sh -c 'echo broken > \"$0\"; exit 1'", &["edit", "board"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), edited);
+
+    let output = vex("true", &["edit", "missing"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not exist"));
+}
+
+#[test]
+fn test_edit_reopen_and_trial_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let run = |editor: &str, args: &[&str], input: &str| {
+        let mut child = Command::new(vex_bin.path())
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env_remove("VISUAL")
+            .env("EDITOR", editor)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = run(
+        "true",
+        &["save", "--format", "toml", "board", "echo", "-m", "1G"],
+        "",
+    );
+    assert!(output.status.success());
+    let config_path = config_dir.join("board.toml");
+
+    // The first edit breaks the file, the second sees the error header and fixes it
+    let editor = temp_dir.path().join("editor.sh");
+    std::fs::write(
+        &editor,
+        "#!/bin/sh\n\
+         if grep -q '^# vex: ' \"$1\"; then\n\
+         \x20 sed -i 's/^qemu_binary/qemu_bin/' \"$1\"\n\
+         else\n\
+         \x20 sed -i 's/^qemu_bin/qemu_binary/; s/1G/4G/' \"$1\"\n\
+         fi\n",
+    )
+    .unwrap();
+    let editor = format!("sh {}", editor.display());
+
+    // Reopen, trial-run, then keep the result
+    let output = run(&editor, &["edit", "board"], "\ny\ny\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Reopen the editor"));
+    assert!(stdout.contains("Trial run"));
+    // The trial run echoes the edited arguments
+    assert!(stdout.contains("-m 4G"), "{}", stdout);
+    assert!(stdout.contains("Configuration 'board' updated"));
+    let edited = std::fs::read_to_string(&config_path).unwrap();
+    assert!(edited.contains("4G"));
+    assert!(!edited.contains("# vex: "));
+
+    // Rejecting the result after the trial run keeps the old version
+    let output = run("sed -i s/4G/8G/", &["edit", "board"], "y\nn\n");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("-m 8G"), "{}", stdout);
+    assert!(stdout.contains("Configuration 'board' left unchanged"));
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), edited);
+
+    // A trial run that QEMU ends with a non-zero status asks without a default
+    let output = run(
+        "true",
+        &["save", "-f", "halt-vm", "sh", "-c", "exit 130"],
+        "",
+    );
+    assert!(output.status.success());
+    let output = run("sed -i s/130/3/", &["edit", "halt-vm"], "y\n\ny\n");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Trial run finished with exit code 3"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Please answer 'y' or 'n'"));
+    assert!(stdout.contains("Configuration 'halt-vm' updated"));
+    let edited = std::fs::read_to_string(config_dir.join("halt-vm.json")).unwrap();
+    assert!(edited.contains("exit 3"));
+}
@@ -0,0 +1,580 @@
+use crate::tests::without_qmp_socket;
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+#[test]
+fn test_exec_debug_with_fixed_gdb_port() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // Use `echo` as a stand-in for QEMU so the final argv is printed
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "debug-vm", "echo", "-m", "2G"])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "debug-vm", "-d", "--gdb-port", "47123"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("-m 2G -gdb tcp::47123 -S"));
+    assert!(stdout.contains("target remote localhost:47123"));
+}
+
+#[test]
+fn test_exec_debug_uses_saved_gdb_port() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "--gdb-port", "auto", "auto-vm", "echo"])
+        .output()
+        .unwrap();
+
+    let config = std::fs::read_to_string(config_dir.join("auto-vm.json")).unwrap();
+    assert!(config.contains("\"gdb_port\": \"auto\""));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "auto-vm", "-d"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("-gdb tcp::"));
+    assert!(!stdout.contains(" -s "));
+}
+
+#[test]
+fn test_exec_gdb_port_requires_debug() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "any-vm", "--gdb-port", "4000"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_exec_from_snapshot_adds_loadvm() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "snap-vm", "echo", "-hda", "disk.qcow2"])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "snap-vm", "--from-snapshot", "clean"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("-hda disk.qcow2 -loadvm clean"));
+}
+
+#[test]
+fn test_exec_dry_run_shell_quoting() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "quote-vm",
+            "qemu-system-x86_64",
+            "-append",
+            "console=ttyS0 root=/dev/vda",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "quote-vm", "--dry-run", "-d", "--gdb-port", "47124"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        without_qmp_socket(stdout.trim()),
+        "qemu-system-x86_64 -append 'console=ttyS0 root=/dev/vda' -gdb tcp::47124 -S"
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "quote-vm", "--dry-run", "--json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let mut argv: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+    let socket = argv.split_off(argv.len() - 2);
+    assert_eq!(socket[0], "-qmp");
+    assert!(socket[1].starts_with("unix:"));
+    assert_eq!(
+        argv,
+        vec![
+            "qemu-system-x86_64",
+            "-append",
+            "console=ttyS0 root=/dev/vda"
+        ]
+    );
+}
+
+#[test]
+fn test_exec_runtime_overrides() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "dev",
+            "qemu-system-x86_64",
+            "-m",
+            "2G",
+            "-device",
+            "virtio-net",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "exec",
+            "dev",
+            "--dry-run",
+            "--",
+            "-m",
+            "8G",
+            "-device",
+            "virtio-rng-pci",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        without_qmp_socket(stdout.trim()),
+        "qemu-system-x86_64 -m 8G -device virtio-net -device virtio-rng-pci"
+    );
+
+    // The saved configuration is left untouched
+    let config = std::fs::read_to_string(config_dir.join("dev.json")).unwrap();
+    assert!(config.contains("2G"));
+    assert!(!config.contains("8G"));
+}
+
+#[test]
+fn test_exec_required_variable_and_qemu_bin_substitution() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "board",
+            "${QEMU_DIR:-/opt/qemu}/qemu-system-riscv64",
+            "-kernel",
+            "${KERNEL:?set KERNEL to the built Image}",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env_remove("KERNEL")
+        .env_remove("QEMU_DIR")
+        .args(["exec", "board", "--dry-run"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'board'"));
+    assert!(stderr.contains("KERNEL"));
+    assert!(stderr.contains("set KERNEL to the built Image"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env("KERNEL", "Image")
+        .env_remove("QEMU_DIR")
+        .args(["exec", "board", "--dry-run"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        without_qmp_socket(stdout.trim()),
+        "/opt/qemu/qemu-system-riscv64 -kernel Image"
+    );
+}
+
+#[test]
+fn test_exec_declared_params() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "board",
+            "--param",
+            "mem:size=2G",
+            "--param",
+            "cpus:int=2",
+            "qemu-system-x86_64",
+            "-m",
+            "${mem}",
+            "-smp",
+            "${cpus}",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "board", "--dry-run", "--set", "mem=4G"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        without_qmp_socket(stdout.trim()),
+        "qemu-system-x86_64 -m 4G -smp 2"
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "board", "--dry-run", "--set", "cpus=many"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'many' is not a valid int value"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["print", "board"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Parameters:"));
+    assert!(stdout.contains("mem <size> = 2G"));
+    assert!(stdout.contains("cpus <int> = 2"));
+}
+
+#[test]
+fn test_exec_port_forwards() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // Keep a host port busy to trigger the conflict check
+    let busy = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let busy_port = busy.local_addr().unwrap().port();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "fwd-vm",
+            "--forward",
+            "auto:22",
+            "--forward",
+            "udp:auto:53",
+            "qemu-system-x86_64",
+            "-netdev",
+            "user,id=n0",
+            "-device",
+            "virtio-net-pci,netdev=n0",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "fwd-vm", "--dry-run", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let argv: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+    let netdev = &argv[2];
+    let re = regex::Regex::new(r"^user,id=n0,hostfwd=tcp::\d+-:22,hostfwd=udp::\d+-:53$").unwrap();
+    assert!(re.is_match(netdev), "unexpected netdev: {}", netdev);
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "busy-vm",
+            "--forward",
+            &format!("{}:22", busy_port),
+            "qemu-system-x86_64",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "busy-vm", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("Host port {} for forward", busy_port)));
+    drop(busy);
+}
+
+#[test]
+fn test_exec_env_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "env-vm",
+            "qemu-system-x86_64",
+            "-kernel",
+            "${VEX_KERNEL}",
+            "-m",
+            "${VEX_MEM}",
+            "-append",
+            "${VEX_CMDLINE}",
+        ])
+        .output()
+        .unwrap();
+
+    // The per-config env file sits next to the JSON and has the lowest priority
+    std::fs::write(
+        config_dir.join("env-vm.env"),
+        "VEX_KERNEL=/shared/Image\nVEX_MEM=1G\nVEX_CMDLINE=quiet\n",
+    )
+    .unwrap();
+    let dev_env = temp_dir.path().join("dev.env");
+    std::fs::write(&dev_env, "VEX_MEM=2G\nVEX_CMDLINE=debug\n").unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env("VEX_CMDLINE", "console=ttyS0")
+        .args(["exec", "env-vm", "--dry-run", "--env-file"])
+        .arg(&dev_env)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        without_qmp_socket(stdout.trim()),
+        "qemu-system-x86_64 -kernel /shared/Image -m 2G -append console=ttyS0"
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env("VEX_CMDLINE", "console=ttyS0")
+        .args(["print", "env-vm", "--resolved", "--env-file"])
+        .arg(&dev_env)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("VEX_CMDLINE = console=ttyS0  (environment)"));
+    assert!(stdout.contains(&format!("VEX_MEM = 2G  (env file {})", dev_env.display())));
+    assert!(stdout.contains("VEX_KERNEL = /shared/Image  (env file"));
+    assert!(
+        stdout.contains("qemu-system-x86_64 -kernel /shared/Image -m 2G -append console=ttyS0")
+    );
+}
+
+#[test]
+fn test_exec_runs_from_saved_working_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    let project = temp_dir.path().join("project");
+    let other = temp_dir.path().join("other");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::write(project.join("disk.txt"), "project disk\n").unwrap();
+    std::fs::write(other.join("disk.txt"), "other disk\n").unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // Saved from inside the project, so the relative path refers to it
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .current_dir(&project)
+        .args(["save", "rel-vm", "sh", "-c", "cat disk.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .current_dir(temp_dir.path())
+        .args(["exec", "rel-vm", "-f"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("project disk"));
+    let canonical = project.canonicalize().unwrap();
+    assert!(stdout.contains(&format!("Working directory: {}", canonical.display())));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "rel-vm", "--cwd"])
+        .arg(&other)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("other disk"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["print", "rel-vm"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&canonical.display().to_string()));
+
+    std::fs::remove_dir_all(&project).unwrap();
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "rel-vm"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not exist. Use '--cwd'"));
+}
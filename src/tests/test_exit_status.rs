@@ -0,0 +1,194 @@
+use crate::tests::save_shell_config;
+use escargot::CargoBuild;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+#[test]
+fn test_exec_passes_through_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "exit-vm", "exit 33");
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "exit-vm"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(33));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("exit code: 33"));
+}
+
+#[test]
+fn test_exec_signal_killed_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "abort-vm", "kill -KILL $$");
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "abort-vm"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(128 + 9));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("signal 9"));
+}
+
+#[test]
+fn test_exec_timeout_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "hang-vm", "exec sleep 30");
+
+    let start = Instant::now();
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "hang-vm", "--timeout", "1s"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(124));
+    assert!(start.elapsed() < Duration::from_secs(10));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("timed out"));
+}
+
+#[test]
+fn test_exec_forwards_sigterm() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "term-vm", "exec sleep 30");
+
+    let mut child = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "term-vm"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // Wait for the instance to be recorded, i.e. QEMU has been started
+    let instances_dir = temp_dir.path().join("state").join("instances");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        let started = std::fs::read_dir(&instances_dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .any(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+            })
+            .unwrap_or(false);
+        if started {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    let status = child.wait().unwrap();
+
+    assert_eq!(status.code(), Some(128 + 15));
+    // vex cleaned up the instance record before exiting
+    let leftover = std::fs::read_dir(&instances_dir)
+        .unwrap()
+        .flatten()
+        .any(|e| e.path().extension().is_some_and(|ext| ext == "json"));
+    assert!(!leftover);
+}
+
+#[test]
+fn test_exec_expect_success_failure_and_timeout() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(
+        &vex_bin,
+        &config_dir,
+        "boot-ok",
+        "printf 'Booting\\nbuildroot login: '; exec sleep 30",
+    );
+    save_shell_config(
+        &vex_bin,
+        &config_dir,
+        "boot-panic",
+        "echo 'Kernel panic - not syncing'; exec sleep 30",
+    );
+    save_shell_config(&vex_bin, &config_dir, "boot-hang", "exec sleep 30");
+    save_shell_config(&vex_bin, &config_dir, "boot-exit", "echo bye");
+
+    let run = |name: &str| {
+        let start = Instant::now();
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args([
+                "exec",
+                name,
+                "--expect",
+                "login:",
+                "--fail-on",
+                "Kernel panic",
+                "--timeout",
+                "2s",
+            ])
+            .output()
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(15));
+        output
+    };
+
+    let output = run("boot-ok");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("buildroot login:"));
+
+    assert_eq!(run("boot-panic").status.code(), Some(122));
+    assert_eq!(run("boot-hang").status.code(), Some(124));
+    assert_eq!(run("boot-exit").status.code(), Some(122));
+}
@@ -0,0 +1,146 @@
+use crate::tests::without_qmp_socket;
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+#[test]
+fn test_save_and_convert_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = vex(&[
+        "save",
+        "--format",
+        "toml",
+        "-d",
+        "toml vm",
+        "-p",
+        "mem:size=1G",
+        "--forward",
+        "2222:22",
+        "--ssh-user",
+        "root",
+        "--pre-exec",
+        "true",
+        "board",
+        "echo",
+        "-m",
+        "${mem}",
+        "-nographic",
+    ]);
+    assert!(output.status.success());
+    assert!(!config_dir.join("board.json").exists());
+    let toml = std::fs::read_to_string(config_dir.join("board.toml")).unwrap();
+    assert!(toml.contains("qemu_bin = \"echo\""));
+
+    let dry_run = |name: &str| {
+        let output = vex(&["exec", name, "--dry-run"]);
+        assert!(output.status.success());
+        without_qmp_socket(String::from_utf8_lossy(&output.stdout).trim()).to_string()
+    };
+    let expected = dry_run("board");
+    assert!(expected.starts_with("echo -m 1G -nographic -nic user,hostfwd=tcp::2222-:22"));
+
+    let output = vex(&["convert", "board", "--to", "yaml"]);
+    assert!(output.status.success());
+    assert!(!config_dir.join("board.toml").exists());
+    assert!(config_dir.join("board.yaml").exists());
+    assert_eq!(dry_run("board"), expected);
+
+    let output = vex(&["convert", "board", "--to", "json"]);
+    assert!(output.status.success());
+    assert!(config_dir.join("board.json").exists());
+    assert_eq!(dry_run("board"), expected);
+
+    // Hand-written files may use comments
+    std::fs::write(
+        config_dir.join("handmade.yaml"),
+        "# Written by hand\nqemu_bin: echo\nargs:\n  - -m   # memory\n  - 512M\ndesc: from yaml\n",
+    )
+    .unwrap();
+    assert_eq!(dry_run("handmade"), "echo -m 512M");
+
+    let output = vex(&["list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("handmade - from yaml\n    Format: yaml"));
+    assert!(stdout.contains("board - toml vm"));
+}
+
+#[test]
+fn test_name_in_several_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(vex(&["save", "dup", "echo"]).status.success());
+    std::fs::write(
+        config_dir.join("dup.toml"),
+        "qemu_bin = \"echo\"\nargs = []\n",
+    )
+    .unwrap();
+
+    let output = vex(&["list"]);
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains("dup - (saved in several formats: dup.json, dup.toml)")
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("WARNING: 'dup' exists in several formats")
+    );
+
+    let output = vex(&["exec", "dup", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Configuration 'dup' exists in several formats (dup.json, dup.toml)")
+    );
+
+    // Saving in one format replaces the others
+    assert!(
+        vex(&["save", "-f", "--format", "yaml", "dup", "echo"])
+            .status
+            .success()
+    );
+    assert!(config_dir.join("dup.yaml").exists());
+    assert!(!config_dir.join("dup.json").exists());
+    assert!(!config_dir.join("dup.toml").exists());
+
+    std::fs::write(
+        config_dir.join("dup.json"),
+        "{\"qemu_bin\": \"echo\", \"args\": []}",
+    )
+    .unwrap();
+    assert!(vex(&["rm", "dup"]).status.success());
+    assert!(!config_dir.join("dup.json").exists());
+    assert!(!config_dir.join("dup.yaml").exists());
+}
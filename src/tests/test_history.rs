@@ -0,0 +1,237 @@
+use crate::tests::save_shell_config;
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+#[test]
+fn test_history_records_runs() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "ok-vm", "exit 0");
+    save_shell_config(&vex_bin, &config_dir, "bad-vm", "exit 7");
+
+    for name in ["ok-vm", "bad-vm"] {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(["exec", name])
+            .output()
+            .unwrap();
+    }
+
+    // Dry runs don't start QEMU and are not recorded
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--dry-run", "ok-vm"])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["history"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().skip(1).collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("ok-vm") && lines[0].contains("ok"));
+    assert!(lines[1].contains("bad-vm") && lines[1].contains("exit 7"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["history", "bad-vm", "--full"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("ok-vm"));
+    assert!(stdout.contains("sh -c 'exit 7'"));
+}
+
+#[test]
+fn test_rerun_replays_recorded_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "replay-vm", "echo variant-1");
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "replay-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // Changing the configuration doesn't affect the recorded run
+    save_shell_config(&vex_bin, &config_dir, "replay-vm", "echo variant-2");
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--rerun", "1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("variant-1"));
+    assert!(stdout.contains("replay of run #1"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--rerun", "42"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No run #42"));
+
+    // The replay itself is recorded as a new run
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["history", "replay-vm"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 3);
+}
+
+#[test]
+fn test_rerun_checks_recorded_ports() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let free_port = || {
+        std::net::TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    };
+    let (ssh_port, gdb_port) = (free_port(), free_port());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "-f", "fwd-vm", "--forward"])
+        .arg(format!("{}:22", ssh_port))
+        .args(["sh", "-c", "exit 0"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "fwd-vm", "-d", "--gdb-port"])
+        .arg(gdb_port.to_string())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let rerun = || {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(["exec", "--rerun", "1"])
+            .output()
+            .unwrap()
+    };
+
+    let busy = std::net::TcpListener::bind(("127.0.0.1", ssh_port)).unwrap();
+    let output = rerun();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "Host port {}/tcp forwarded by run #1 is already in use",
+            ssh_port
+        )),
+        "unexpected error: {}",
+        stderr
+    );
+    drop(busy);
+
+    let busy = std::net::TcpListener::bind(("127.0.0.1", gdb_port)).unwrap();
+    let output = rerun();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&format!(
+        "GDB port {} used by run #1 is already in use",
+        gdb_port
+    )));
+    drop(busy);
+
+    assert!(rerun().status.success());
+}
+
+#[test]
+fn test_concurrent_runs_get_distinct_ids() {
+    const RUNS: u64 = 16;
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // Every run waits for the start file, so their records are appended at about the same time
+    let start = temp_dir.path().join("start");
+    let script = format!("while [ ! -e {} ]; do sleep 0.01; done", start.display());
+    save_shell_config(&vex_bin, &config_dir, "busy-vm", &script);
+
+    let runs: Vec<_> = (0..RUNS)
+        .map(|_| {
+            vex_bin
+                .command()
+                .env("VEX_CONFIG_DIR", &config_dir)
+                .args(["exec", "busy-vm"])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    std::fs::write(&start, "").unwrap();
+    for mut run in runs {
+        assert!(run.wait().unwrap().success());
+    }
+
+    let history = std::fs::read_to_string(temp_dir.path().join("state/history.jsonl")).unwrap();
+    let mut ids: Vec<u64> = history
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line).unwrap()["id"]
+                .as_u64()
+                .unwrap()
+        })
+        .collect();
+    ids.sort_unstable();
+    assert_eq!(ids, (1..=RUNS).collect::<Vec<_>>());
+}
@@ -0,0 +1,205 @@
+use escargot::CargoBuild;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_hooks_run_around_qemu() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let pre_marker = temp_dir.path().join("pre");
+    let post_marker = temp_dir.path().join("post");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "-p",
+            "label=hello",
+            "--pre-exec",
+            &format!("echo $label-$VEX_CONFIG > {}", pre_marker.display()),
+            "--post-exec",
+            &format!("echo $label:$VEX_EXIT_CODE > {}", post_marker.display()),
+            "hook-vm",
+            "sh",
+            "-c",
+            "exit 5",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "hook-vm", "--set", "label=world"])
+        .output()
+        .unwrap();
+
+    // QEMU's exit status is kept, and post-exec hooks still ran
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(fs::read_to_string(&pre_marker).unwrap(), "world-hook-vm\n");
+    assert_eq!(fs::read_to_string(&post_marker).unwrap(), "world:5\n");
+}
+
+#[test]
+fn test_failing_pre_hook_aborts() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let qemu_marker = temp_dir.path().join("qemu");
+    let post_marker = temp_dir.path().join("post");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "--pre-exec",
+            "exit 9",
+            "--post-exec",
+            &format!("touch {}", post_marker.display()),
+            "abort-vm",
+            "sh",
+            "-c",
+            &format!("touch {}", qemu_marker.display()),
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "abort-vm"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("pre-exec hook 'exit 9' failed with exit code: 9"));
+    assert!(!qemu_marker.exists());
+    assert!(post_marker.exists());
+}
+
+#[test]
+fn test_post_hooks_run_when_detached_instance_exits() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let post_marker = temp_dir.path().join("post");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "--post-exec",
+            &format!("echo cleaned >> {}", post_marker.display()),
+            "poweroff-vm",
+            "sh",
+            "-c",
+            "sleep 1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--detach", "poweroff-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!post_marker.exists());
+
+    // The guest powers off on its own
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+
+    // Looking up instances doesn't clean up after them as a side effect
+    for args in [
+        &["logs", "poweroff-vm"][..],
+        &["qmp", "other-vm", "query-status"],
+    ] {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap();
+    }
+    assert!(!post_marker.exists());
+
+    // `vex ps` does, and concurrent ones run the hooks only once
+    let listings: Vec<_> = (0..4)
+        .map(|_| {
+            vex_bin
+                .command()
+                .env("VEX_CONFIG_DIR", &config_dir)
+                .args(["ps"])
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    let stdout: String = listings
+        .into_iter()
+        .map(|ps| String::from_utf8_lossy(&ps.wait_with_output().unwrap().stdout).into_owned())
+        .collect();
+    assert_eq!(
+        stdout.matches("Removed stale instance").count(),
+        1,
+        "{}",
+        stdout
+    );
+    assert_eq!(fs::read_to_string(&post_marker).unwrap(), "cleaned\n");
+
+    // Hooks run once, not again on the next prune
+    fs::remove_file(&post_marker).unwrap();
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["ps"])
+        .output()
+        .unwrap();
+    assert!(!post_marker.exists());
+
+    // Stopping an instance that already exited just cleans up after it
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--detach", "poweroff-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["stop", "poweroff-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("had already exited"));
+    assert_eq!(fs::read_to_string(&post_marker).unwrap(), "cleaned\n");
+}
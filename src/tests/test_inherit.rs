@@ -0,0 +1,219 @@
+use crate::tests::without_qmp_socket;
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+#[test]
+fn test_extends_merges_parent_args() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let base_args = [
+        "save",
+        "-f",
+        "base",
+        "echo",
+        "-machine",
+        "virt",
+        "-m",
+        "1G",
+        "-nographic",
+        "-device",
+        "virtio-rng-pci",
+        "-drive",
+        "file=base.img,if=virtio",
+    ];
+    assert!(vex(&base_args).status.success());
+    let output = vex(&[
+        "save",
+        "--extends",
+        "base",
+        "--remove-arg",
+        "-nographic",
+        "--replace-arg",
+        "-drive file=a.img,if=virtio",
+        "board-a",
+        "echo",
+        "-m",
+        "2G",
+        "-device",
+        "e1000",
+    ]);
+    assert!(output.status.success());
+
+    // The child follows the parent's binary instead of recording its own
+    let child = std::fs::read_to_string(config_dir.join("board-a.json")).unwrap();
+    let child: serde_json::Value = serde_json::from_str(&child).unwrap();
+    assert_eq!(child["qemu_bin"], "");
+    assert_eq!(child["extends"], "base");
+
+    let output = vex(&["exec", "board-a", "--dry-run"]);
+    assert!(output.status.success());
+    assert_eq!(
+        without_qmp_socket(String::from_utf8_lossy(&output.stdout).trim()),
+        "echo -machine virt -m 2G -device virtio-rng-pci -drive file=a.img,if=virtio -device e1000"
+    );
+
+    // Edits to the base flow into the child
+    let mut edited = base_args.to_vec();
+    edited.extend(["-smp", "2"]);
+    assert!(vex(&edited).status.success());
+    let output = vex(&["exec", "board-a", "--dry-run"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("-smp 2"));
+
+    let output = vex(&["print", "board-a"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Extends:\n  board-a -> base"));
+    assert!(stdout.contains("[0] -machine virt  (base)"));
+    assert!(stdout.contains("[1] -m 2G  (board-a)"));
+    assert!(stdout.contains("[3] -drive file=a.img,if=virtio  (board-a)"));
+
+    // Renaming the parent keeps the child attached
+    assert!(vex(&["rename", "base", "base-virt"]).status.success());
+    let output = vex(&["exec", "board-a", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("-machine virt -m 2G"));
+}
+
+#[test]
+fn test_extends_cycles_and_missing_parents() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(vex(&["save", "a", "echo"]).status.success());
+    assert!(
+        vex(&["save", "--extends", "a", "b", "echo"])
+            .status
+            .success()
+    );
+
+    let output = vex(&["save", "-f", "--extends", "b", "a", "echo"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Configuration inheritance cycle: a -> b -> a")
+    );
+
+    // Cycles written by hand are caught when loading
+    std::fs::write(
+        config_dir.join("a.json"),
+        r#"{"extends": "b", "args": [], "desc": null, "qemu_version": null}"#,
+    )
+    .unwrap();
+    let output = vex(&["exec", "b", "--dry-run"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Configuration inheritance cycle: b -> a -> b")
+    );
+
+    let output = vex(&["save", "--extends", "missing", "c", "echo"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Configuration 'c' extends 'missing', which does not exist")
+    );
+}
+
+#[test]
+fn test_extends_keeps_parent_paths_relative_to_parent_cwd() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    let base_dir = temp_dir.path().join("base");
+    let board_dir = temp_dir.path().join("board");
+    std::fs::create_dir_all(&base_dir).unwrap();
+    std::fs::create_dir_all(&board_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |dir: &std::path::Path, args: &[&str]| {
+        vex_bin
+            .command()
+            .current_dir(dir)
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = vex(
+        &base_dir,
+        &[
+            "save",
+            "base",
+            "echo",
+            "-kernel",
+            "./bzImage",
+            "-drive",
+            "file=rootfs.qcow2,if=virtio",
+            "-device",
+            "loader,file=../fw/boot.bin",
+        ],
+    );
+    assert!(output.status.success());
+    let output = vex(
+        &board_dir,
+        &[
+            "save",
+            "--extends",
+            "base",
+            "--remove-arg",
+            "-device loader,file=../fw/boot.bin",
+            "board",
+            "echo",
+            "-initrd",
+            "./initrd.img",
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The child runs from its own directory, the parent's files stay where they were
+    let base_dir = base_dir.canonicalize().unwrap();
+    let output = vex(temp_dir.path(), &["exec", "board", "--dry-run"]);
+    assert!(output.status.success());
+    assert_eq!(
+        without_qmp_socket(String::from_utf8_lossy(&output.stdout).trim()),
+        format!(
+            "echo -kernel {0}/bzImage -drive file={0}/rootfs.qcow2,if=virtio -initrd ./initrd.img",
+            base_dir.display()
+        )
+    );
+}
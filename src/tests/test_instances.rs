@@ -0,0 +1,193 @@
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+#[test]
+fn test_detach_ps_logs_stop() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // A shell loop stands in for a long-running QEMU process
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "bg-vm", "sh", "-c", "echo booted; exec sleep 30"])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--detach", "bg-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Started instance 'bg-vm-"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .arg("ps")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("bg-vm"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["logs", "bg-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("booted"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["stop", "bg-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .arg("ps")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    assert!(stdout.contains("no running instances"));
+
+    // The log of a stopped instance is still available
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["logs", "bg-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("booted"));
+}
+
+#[test]
+fn test_ps_removes_stale_instances() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    let instances_dir = temp_dir.path().join("state").join("instances");
+    std::fs::create_dir_all(&instances_dir).unwrap();
+
+    // pid_max never exceeds 2^22, so this pid cannot exist
+    let record = r#"{
+        "id": "ghost-99999999",
+        "config": "ghost",
+        "pid": 99999999,
+        "qemu_bin": "qemu-system-x86_64",
+        "args": [],
+        "started_at": 0,
+        "log_file": "/nonexistent.log",
+        "gdb_port": null
+    }"#;
+    std::fs::write(instances_dir.join("ghost-99999999.json"), record).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .arg("ps")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Removed stale instance 'ghost-99999999'"));
+    assert!(!instances_dir.join("ghost-99999999.json").exists());
+}
+
+#[test]
+fn test_stop_unknown_instance() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["stop", "nothing"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr).to_lowercase();
+    assert!(stderr.contains("no running instance"));
+}
+
+#[test]
+fn test_reused_pid_is_not_signalled() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    let instances_dir = temp_dir.path().join("state").join("instances");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(
+        vex(&["save", "reuse-vm", "sh", "-c", "exec sleep 30"])
+            .status
+            .success()
+    );
+    assert!(vex(&["exec", "--detach", "reuse-vm"]).status.success());
+
+    // Pretend QEMU died and its pid now belongs to another process started later
+    let record_path = std::fs::read_dir(&instances_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .unwrap();
+    let mut record: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&record_path).unwrap()).unwrap();
+    let pid = record["pid"].as_u64().unwrap() as u32;
+    let start_time = record["start_time"].as_u64().unwrap();
+    record["start_time"] = serde_json::json!(start_time - 1);
+    std::fs::write(&record_path, record.to_string()).unwrap();
+
+    // The instance counts as exited: its record is cleaned up, the other process left alone
+    let output = vex(&["kill", "reuse-vm"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("had already exited"));
+    assert!(crate::utils::process::is_process_alive(pid));
+    assert!(!record_path.exists());
+
+    crate::utils::process::send_signal(pid, libc::SIGKILL).unwrap();
+}
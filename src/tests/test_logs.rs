@@ -0,0 +1,311 @@
+use crate::tests::save_shell_config;
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+fn run_logs(vex_bin: &escargot::CargoRun, config_dir: &std::path::Path, args: &[&str]) -> String {
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", config_dir)
+        .arg("logs")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_foreground_runs_are_logged() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(
+        &vex_bin,
+        &config_dir,
+        "log-vm",
+        "echo run-$RUN_NO; echo oops >&2",
+    );
+
+    for run_no in ["1", "2"] {
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env("RUN_NO", run_no)
+            .args(["exec", "log-vm"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        // Output is still shown live
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains(&format!("run-{}", run_no)));
+        // Log names have second resolution; keep the runs apart so they sort in order
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+    }
+
+    let logs_dir = temp_dir.path().join("state").join("logs").join("log-vm");
+    assert_eq!(std::fs::read_dir(&logs_dir).unwrap().count(), 2);
+
+    let latest = run_logs(&vex_bin, &config_dir, &["log-vm"]);
+    assert!(latest.contains("run-2"));
+    assert!(latest.contains("oops"));
+
+    let previous = run_logs(&vex_bin, &config_dir, &["log-vm", "--run", "2"]);
+    assert!(previous.contains("run-1"));
+
+    let list = run_logs(&vex_bin, &config_dir, &["log-vm", "--list"]);
+    assert!(list.contains("#1"));
+    assert!(list.contains("#2"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["logs", "log-vm", "--run", "3"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_run_logs_are_pruned() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(&vex_bin, &config_dir, "prune-vm", "echo hi");
+
+    for _ in 0..3 {
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env("VEX_KEEP_LOGS", "2")
+            .args(["exec", "prune-vm"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    }
+
+    let logs_dir = temp_dir.path().join("state").join("logs").join("prune-vm");
+    assert_eq!(std::fs::read_dir(&logs_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn test_prune_keeps_logs_of_running_instances() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(
+        &vex_bin,
+        &config_dir,
+        "long-vm",
+        "echo hi; if [ -n \"$VEX_TEST_LONG\" ]; then exec sleep 30; fi",
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env("VEX_TEST_LONG", "1")
+        .args(["exec", "--detach", "long-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let logs_dir = temp_dir.path().join("state").join("logs").join("long-vm");
+    let detached_log = std::fs::read_dir(&logs_dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+
+    // Neither the detached instance's log nor the new run's log is pruned
+    for _ in 0..2 {
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env("VEX_KEEP_LOGS", "0")
+            .args(["exec", "long-vm"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("VEX_KEEP_LOGS must be at least 1")
+        );
+    }
+    assert!(detached_log.exists());
+    assert_eq!(std::fs::read_dir(&logs_dir).unwrap().count(), 2);
+    assert!(run_logs(&vex_bin, &config_dir, &["long-vm", "--run", "1"]).contains("hi"));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["kill", "long-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_logs_unknown_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["logs", "nope"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No logs found"));
+}
+
+#[test]
+fn test_logs_of_exited_instance() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(
+        &vex_bin,
+        &config_dir,
+        "gone-vm",
+        "echo run-$RUN_NO; exec sleep 30",
+    );
+
+    // Two detached runs, both stopped again
+    let mut ids = Vec::new();
+    for run_no in ["1", "2"] {
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env("RUN_NO", run_no)
+            .args(["exec", "--detach", "gone-vm"])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let id = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("Started instance '"))
+            .and_then(|rest| rest.split('\'').next())
+            .expect("instance ID in output")
+            .to_string();
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(["stop", &id])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        ids.push(id);
+    }
+
+    // An exited instance's log is still found by its ID
+    assert_eq!(run_logs(&vex_bin, &config_dir, &[&ids[0]]), "run-1\n");
+    assert_eq!(run_logs(&vex_bin, &config_dir, &[&ids[1]]), "run-2\n");
+    assert_eq!(run_logs(&vex_bin, &config_dir, &["gone-vm"]), "run-2\n");
+}
+
+#[test]
+fn test_logs_with_several_running_instances() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    save_shell_config(
+        &vex_bin,
+        &config_dir,
+        "multi-vm",
+        "echo run-$RUN_NO; exec sleep 30",
+    );
+
+    let mut ids = Vec::new();
+    for run_no in ["1", "2"] {
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .env("RUN_NO", run_no)
+            .args(["exec", "--detach", "multi-vm"])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        ids.push(
+            stdout
+                .lines()
+                .find_map(|line| line.strip_prefix("Started instance '"))
+                .and_then(|rest| rest.split('\'').next())
+                .expect("instance ID in output")
+                .to_string(),
+        );
+        // Log file names have a one-second timestamp
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+    }
+
+    // `--run` names a log, so several running instances don't get in the way
+    assert_eq!(
+        run_logs(&vex_bin, &config_dir, &["multi-vm", "--run", "2"]),
+        "run-1\n"
+    );
+    assert_eq!(run_logs(&vex_bin, &config_dir, &["multi-vm"]), "run-2\n");
+
+    // Following is ambiguous without an instance ID or run number
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["logs", "multi-vm", "-f"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("several running instances"));
+
+    for id in &ids {
+        let output = vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(["kill", id])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    }
+}
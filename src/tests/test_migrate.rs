@@ -0,0 +1,128 @@
+use crate::tests::without_qmp_socket;
+use escargot::CargoBuild;
+use tempfile::TempDir;
+
+#[test]
+fn test_migrate_all_upgrades_old_configs() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // Written before configurations carried a schema version
+    let old_json =
+        r#"{"qemu_bin": "echo", "args": ["-m", "1G"], "desc": "old", "qemu_version": null}"#;
+    std::fs::write(config_dir.join("old-vm.json"), old_json).unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "new-vm", "echo"])
+        .output()
+        .unwrap();
+    let new_json = std::fs::read_to_string(config_dir.join("new-vm.json")).unwrap();
+    assert!(new_json.contains("\"schema_version\": 1"));
+
+    // Old files load without being rewritten
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "old-vm", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        without_qmp_socket(String::from_utf8_lossy(&output.stdout).trim()),
+        "echo -m 1G"
+    );
+    assert_eq!(
+        std::fs::read_to_string(config_dir.join("old-vm.json")).unwrap(),
+        old_json
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["migrate", "--all"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Migrated 'old-vm' from schema version 0 to 1"));
+    assert!(stdout.contains("'new-vm' is already at schema version 1"));
+
+    let migrated = std::fs::read_to_string(config_dir.join("old-vm.json")).unwrap();
+    assert!(migrated.contains("\"schema_version\": 1"));
+    assert!(migrated.contains("\"desc\": \"old\""));
+    assert_eq!(
+        std::fs::read_to_string(config_dir.join("old-vm.json.v0.bak")).unwrap(),
+        old_json
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["list"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("old-vm - old"));
+    assert!(!stdout.contains(".bak"));
+}
+
+#[test]
+fn test_newer_schema_is_refused() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let future_json = r#"{"schema_version": 99, "qemu_bin": "echo", "args": []}"#;
+    std::fs::write(config_dir.join("future-vm.json"), future_json).unwrap();
+    std::fs::write(
+        config_dir.join("old-vm.json"),
+        r#"{"qemu_bin": "echo", "args": [], "desc": null, "qemu_version": null}"#,
+    )
+    .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "future-vm", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(
+        "Configuration 'future-vm' uses schema version 99, but this Vex only supports up to version 1"
+    ));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["migrate", "--all"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Skipped 'future-vm'"));
+    assert!(stderr.contains("1 of 2 configurations could not be migrated"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Migrated 'old-vm'"));
+
+    // Files from a newer Vex are left alone
+    assert_eq!(
+        std::fs::read_to_string(config_dir.join("future-vm.json")).unwrap(),
+        future_json
+    );
+    assert!(!config_dir.join("future-vm.json.v99.bak").exists());
+}
@@ -152,3 +152,99 @@ fn test_save_multiple_configs() {
     assert!(config_dir.join("vm2.json").exists());
     assert!(config_dir.join("vm3.json").exists());
 }
+
+#[test]
+fn test_save_debug_flags_use_option_table() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // `-S` as the value of `-append` is not a debug flag and must survive
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "dbg-vm", "echo", "-s", "-append", "-S", "-S"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Debug parameters have been skipped"));
+
+    let config = std::fs::read_to_string(config_dir.join("dbg-vm.json")).unwrap();
+    let config: serde_json::Value = serde_json::from_str(&config).unwrap();
+    assert_eq!(config["args"], serde_json::json!(["-append", "-S"]));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "bad-vm", "echo", "-nographic", "-kernel"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("QEMU option '-kernel' requires a value"));
+    assert!(!config_dir.join("bad-vm.json").exists());
+}
+
+#[test]
+fn test_save_param_defaults_and_help() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let vex = |args: &[&str]| {
+        vex_bin
+            .command()
+            .env("VEX_CONFIG_DIR", &config_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    // A default that can't be used is rejected when saving, not on the first exec
+    let output = vex(&[
+        "save",
+        "-p",
+        "mem:size=banana",
+        "bad-vm",
+        "echo",
+        "-m",
+        "${mem}",
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'banana' is not a valid size value"),
+        "{}",
+        stderr
+    );
+    assert!(!config_dir.join("bad-vm.json").exists());
+
+    let output = vex(&[
+        "save",
+        "-p",
+        "mem:size=2G#Guest memory",
+        "good-vm",
+        "echo",
+        "-m",
+        "${mem}",
+    ]);
+    assert!(output.status.success());
+    let output = vex(&["print", "good-vm"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("mem <size> = 2G\n      Guest memory"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,173 @@
+use escargot::CargoBuild;
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use crate::utils::process::{is_process_alive, send_signal, wait_for_exit};
+
+#[test]
+fn test_sidecar_started_and_stopped_with_qemu() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let pid_file = temp_dir.path().join("sidecar.pid");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    // The sidecar stands in for virtiofsd: it creates its socket and keeps running
+    let sidecar = format!(
+        r#"fs=sh -c "echo \$\$ > {}; touch ${{socket}}; exec sleep 30""#,
+        pid_file.display()
+    );
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "--sidecar",
+            &sidecar,
+            "fs-vm",
+            "sh",
+            "-c",
+            "test -e ${sidecar.fs} && echo socket-ready",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "fs-vm"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("socket-ready"));
+
+    // The sidecar is gone once QEMU exited
+    let pid: u32 = fs::read_to_string(&pid_file)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+    assert!(!is_process_alive(pid));
+}
+
+#[test]
+fn test_sidecar_failure_aborts_exec() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let qemu_marker = temp_dir.path().join("qemu");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "--sidecar",
+            "tpm=sh -c 'echo no tpm for you; exit 4'",
+            "tpm-vm",
+            "sh",
+            "-c",
+            &format!("touch {}", qemu_marker.display()),
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "tpm-vm"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Sidecar 'tpm' exited with exit code 4"));
+    assert!(!qemu_marker.exists());
+
+    // The sidecar's output ends up in the run log
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["logs", "tpm-vm"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("no tpm for you"));
+}
+
+#[test]
+fn test_sidecar_stopped_when_detached_qemu_dies() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    fs::create_dir_all(&config_dir).unwrap();
+    let sidecar_pid_file = temp_dir.path().join("sidecar.pid");
+    let qemu_pid_file = temp_dir.path().join("qemu.pid");
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+    let read_pid = |path: &std::path::Path| -> u32 {
+        fs::read_to_string(path).unwrap().trim().parse().unwrap()
+    };
+
+    let sidecar = format!(
+        r#"tpm=sh -c "echo \$\$ > {}; touch ${{socket}}; exec sleep 30""#,
+        sidecar_pid_file.display()
+    );
+    let qemu = format!("echo $$ > {}; exec sleep 30", qemu_pid_file.display());
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "--sidecar", &sidecar, "crash-vm", "sh", "-c", &qemu])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--detach", "crash-vm"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let sidecar_pid = read_pid(&sidecar_pid_file);
+    assert!(is_process_alive(sidecar_pid));
+
+    // QEMU dies behind vex's back
+    let qemu_pid = read_pid(&qemu_pid_file);
+    send_signal(qemu_pid, libc::SIGKILL).unwrap();
+    assert!(wait_for_exit(qemu_pid, Duration::from_secs(5)));
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["ps"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Removed stale instance"), "{}", stdout);
+    assert!(stdout.contains("No running instances."));
+    assert!(!is_process_alive(sidecar_pid));
+}
@@ -0,0 +1,102 @@
+use escargot::CargoBuild;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[test]
+fn test_snapshot_requires_qcow2_drive() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "kernel-vm", "echo", "-kernel", "bzImage"])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["snapshot", "create", "kernel-vm", "clean"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no qcow2 drives"));
+}
+
+#[test]
+fn test_offline_snapshot_uses_config_cwd_and_env_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    let work_dir = temp_dir.path().join("work");
+    let bin_dir = work_dir.join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let calls = temp_dir.path().join("qemu-img.calls");
+
+    // A stand-in qemu-img next to the QEMU binary records how it was called
+    let qemu_img = bin_dir.join("qemu-img");
+    std::fs::write(
+        &qemu_img,
+        format!("#!/bin/sh\necho \"$@\" >> {}\n", calls.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&qemu_img, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::write(config_dir.join("disk-vm.env"), "DISK=./disk.qcow2\n").unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .current_dir(&work_dir)
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "disk-vm",
+            "./bin/qemu-system-x86_64",
+            "-drive",
+            "file=${DISK},if=virtio",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Run from elsewhere: the drive still resolves against the saved directory
+    let output = vex_bin
+        .command()
+        .current_dir(temp_dir.path())
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["snapshot", "create", "disk-vm", "clean"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let work_dir = work_dir.canonicalize().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&calls).unwrap(),
+        format!(
+            "snapshot -c clean {}\n",
+            work_dir.join("./disk.qcow2").display()
+        )
+    );
+}
@@ -0,0 +1,134 @@
+use escargot::CargoBuild;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[test]
+fn test_ssh_uses_forwarded_port_and_settings() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    // A fake ssh that prints the arguments it was called with
+    let bin_dir = temp_dir.path().join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let fake_ssh = bin_dir.join("ssh");
+    std::fs::write(&fake_ssh, "#!/bin/sh\necho \"ssh $*\"\n").unwrap();
+    std::fs::set_permissions(&fake_ssh, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args([
+            "save",
+            "--ssh-user",
+            "root",
+            "--ssh-key",
+            "/keys/id_vm",
+            "ssh-vm",
+            "sh",
+            "-c",
+            "exec sleep 30",
+        ])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--detach", "-f", "ssh-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // Guest port 22 is forwarded automatically and reported
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let re = regex::Regex::new(r"Forward: tcp localhost:(\d+) -> guest:22").unwrap();
+    let port = re.captures(&stdout).expect("forward not reported")[1].to_string();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env("PATH", &path)
+        .args(["ssh", "ssh-vm", "--", "uname", "-a"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains(&format!("-p {}", port)));
+    assert!(stdout.contains("StrictHostKeyChecking=no"));
+    assert!(stdout.contains("-i /keys/id_vm"));
+    assert!(stdout.contains("root@127.0.0.1 -- uname -a"));
+
+    // Nothing answers on the forwarded port, so waiting times out
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .env("PATH", &path)
+        .args(["ssh", "ssh-vm", "--wait", "1s"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("did not answer"));
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["kill", "ssh-vm"])
+        .output()
+        .unwrap();
+}
+
+#[test]
+fn test_ssh_without_forward() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join(".vex");
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let vex_bin = CargoBuild::new()
+        .bin("vex")
+        .current_release()
+        .run()
+        .unwrap();
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["save", "plain-vm", "sh", "-c", "exec sleep 30"])
+        .output()
+        .unwrap();
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["exec", "--detach", "plain-vm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["ssh", "plain-vm"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no host port forwarded to guest port 22"));
+
+    vex_bin
+        .command()
+        .env("VEX_CONFIG_DIR", &config_dir)
+        .args(["kill", "plain-vm"])
+        .output()
+        .unwrap();
+}
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Result of matching QEMU output against `--expect` and `--fail-on` patterns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectOutcome {
+    /// An `--expect` pattern matched
+    Matched(String),
+    /// A `--fail-on` pattern matched
+    Failed(String),
+}
+
+/// Incremental matcher for output that arrives in arbitrary chunks
+pub struct ExpectMatcher {
+    expect: Vec<Regex>,
+    fail_on: Vec<Regex>,
+    /// Output not yet ruled out: the current, unfinished line
+    window: String,
+}
+
+impl ExpectMatcher {
+    pub fn new(expect: &[String], fail_on: &[String]) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).with_context(|| format!("Invalid pattern '{}'", p)))
+                .collect()
+        };
+        Ok(ExpectMatcher {
+            expect: compile(expect)?,
+            fail_on: compile(fail_on)?,
+            window: String::new(),
+        })
+    }
+
+    /// Whether any `--expect` pattern was given
+    pub fn has_expect(&self) -> bool {
+        !self.expect.is_empty()
+    }
+
+    /// Feed a chunk of output, returning the earliest pattern match within a line.
+    ///
+    /// Prompts such as `login:` have no trailing newline, so the unfinished line
+    /// is kept and matched again as more output arrives.
+    pub fn feed(&mut self, chunk: &str) -> Option<ExpectOutcome> {
+        self.window.push_str(chunk);
+
+        let mut earliest: Option<(usize, ExpectOutcome)> = None;
+        let candidates = self
+            .fail_on
+            .iter()
+            .map(|re| (re, true))
+            .chain(self.expect.iter().map(|re| (re, false)));
+        for (re, is_failure) in candidates {
+            if let Some(m) = re.find(&self.window)
+                && earliest.as_ref().is_none_or(|(pos, _)| m.start() < *pos)
+            {
+                let pattern = re.as_str().to_string();
+                let outcome = if is_failure {
+                    ExpectOutcome::Failed(pattern)
+                } else {
+                    ExpectOutcome::Matched(pattern)
+                };
+                earliest = Some((m.start(), outcome));
+            }
+        }
+        if let Some((_, outcome)) = earliest {
+            return Some(outcome);
+        }
+
+        // Complete lines can no longer match; keep only the unfinished one
+        if let Some(pos) = self.window.rfind('\n') {
+            self.window.drain(..=pos);
+        }
+        None
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::utils::process::exit_code;
+
+/// Run pre-exec hooks in order, stopping at the first one that fails
+pub fn run_pre_hooks(
+    hooks: &[String],
+    env: &BTreeMap<String, String>,
+    cwd: Option<&Path>,
+) -> Result<()> {
+    for hook in hooks {
+        run_hook("pre-exec", hook, env, cwd)?;
+    }
+    Ok(())
+}
+
+/// Run every post-exec hook, even after a failure, and report the first failure
+pub fn run_post_hooks(
+    hooks: &[String],
+    env: &BTreeMap<String, String>,
+    cwd: Option<&Path>,
+) -> Result<()> {
+    let mut first_error = None;
+    for hook in hooks {
+        if let Err(err) = run_hook("post-exec", hook, env, cwd) {
+            eprintln!("WARNING: {:#}", err);
+            first_error.get_or_insert(err);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Run one hook command with `sh -c` from `cwd`, with `env` added to its environment
+fn run_hook(
+    stage: &str,
+    hook: &str,
+    env: &BTreeMap<String, String>,
+    cwd: Option<&Path>,
+) -> Result<()> {
+    println!("Running {} hook: {}", stage, hook);
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(hook).envs(env);
+    if let Some(dir) = cwd {
+        command.current_dir(dir);
+    }
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run {} hook '{}'", stage, hook))?;
+    if !status.success() {
+        anyhow::bail!(
+            "{} hook '{}' failed with exit code: {}",
+            stage,
+            hook,
+            exit_code(status)
+        );
+    }
+    Ok(())
+}
@@ -24,3 +24,30 @@ pub fn prompt_user_default_no() -> Result<bool> {
     // For overwrite prompts: only explicit "y" or "yes" returns true
     Ok(input == "y" || input == "yes")
 }
+
+/// Prompt user for yes/no input with default yes, but treat end of input as no
+/// (so a prompt in a loop can't repeat forever without a terminal)
+pub fn prompt_user_eof_no() -> Result<bool> {
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        return Ok(false);
+    }
+    let input = input.trim().to_lowercase();
+    Ok(input.is_empty() || input == "y" || input == "yes")
+}
+
+/// Prompt user for an explicit yes or no without a default, asking again on
+/// any other input; end of input counts as no
+pub fn prompt_user_explicit() -> Result<bool> {
+    loop {
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(false);
+        }
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer 'y' or 'n'"),
+        }
+    }
+}
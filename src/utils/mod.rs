@@ -1,2 +1,13 @@
+pub mod expect;
+pub mod hooks;
 pub mod io;
+pub mod net;
+pub mod process;
 pub mod qemu;
+pub mod qemu_args;
+pub mod qemu_options;
+pub mod qmp;
+pub mod shell;
+pub mod sidecar;
+pub mod snapshot;
+pub mod time;
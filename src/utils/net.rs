@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Protocol;
+
+/// Address QEMU listens on for `hostfwd=tcp::PORT-...` and `-gdb tcp::PORT`.
+///
+/// Probing only localhost would miss a port taken on another interface.
+const ANY_ADDR: &str = "0.0.0.0";
+
+/// Check whether a TCP port is currently free to bind on all interfaces
+pub fn is_port_free(port: u16) -> bool {
+    is_addr_port_free(Protocol::Tcp, "", port)
+}
+
+/// Check whether a port is free to bind on all interfaces for the given protocol
+pub fn is_proto_port_free(proto: Protocol, port: u16) -> bool {
+    is_addr_port_free(proto, "", port)
+}
+
+/// Check whether a port is free to bind on a host address, where an empty address
+/// means all interfaces as in a `hostfwd=` rule
+pub fn is_addr_port_free(proto: Protocol, addr: &str, port: u16) -> bool {
+    let addr = if addr.is_empty() { ANY_ADDR } else { addr };
+    match proto {
+        Protocol::Tcp => TcpListener::bind((addr, port)).is_ok(),
+        Protocol::Udp => UdpSocket::bind((addr, port)).is_ok(),
+    }
+}
+
+/// Ask the OS for a port that is free on all interfaces for the given protocol
+pub fn find_free_proto_port(proto: Protocol) -> Result<u16> {
+    match proto {
+        Protocol::Tcp => find_free_port(),
+        Protocol::Udp => {
+            let socket =
+                UdpSocket::bind((ANY_ADDR, 0)).context("Failed to allocate a free UDP port")?;
+            Ok(socket
+                .local_addr()
+                .context("Failed to read allocated UDP port")?
+                .port())
+        }
+    }
+}
+
+/// Ask the OS for a TCP port that is free on all interfaces
+pub fn find_free_port() -> Result<u16> {
+    let listener =
+        TcpListener::bind((ANY_ADDR, 0)).context("Failed to allocate a free TCP port")?;
+    let port = listener
+        .local_addr()
+        .context("Failed to read allocated TCP port")?
+        .port();
+    Ok(port)
+}
+
+/// Wait until an SSH server at `addr:port` sends its banner.
+///
+/// A user-mode network accepts forwarded connections even before the guest
+/// listens, so an accepted connection alone doesn't mean sshd is up.
+pub fn wait_for_ssh(addr: &str, port: u16, timeout: Duration) -> bool {
+    // A timeout too far in the future to represent never expires
+    let deadline = Instant::now().checked_add(timeout);
+    while deadline.is_none_or(|deadline| Instant::now() < deadline) {
+        if ssh_banner_received(addr, port) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    false
+}
+
+/// Connect once and check whether the peer greets with an SSH banner
+fn ssh_banner_received(addr: &str, port: u16) -> bool {
+    let Some(target) = (addr, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut a| a.next())
+    else {
+        return false;
+    };
+    let Ok(mut stream) = TcpStream::connect_timeout(&target, Duration::from_secs(2)) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(3)));
+    let mut banner = [0u8; 4];
+    stream.read_exact(&mut banner).is_ok() && &banner == b"SSH-"
+}
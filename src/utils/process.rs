@@ -0,0 +1,119 @@
+use anyhow::Result;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Check whether a process with the given pid exists
+pub fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 performs error checking only; EPERM still means the process exists
+    let ret = unsafe { libc::kill(pid, 0) };
+    let exists = ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+    exists && !is_zombie(pid)
+}
+
+/// Start time of a process in clock ticks since boot (Linux only).
+///
+/// Pids are reused, so the pid and start time together identify a process.
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesised command name start at field 3; the start time is field 22
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Check whether `pid` is alive and still the process that started at `start_time`
+pub fn is_same_process(pid: u32, start_time: Option<u64>) -> bool {
+    is_process_alive(pid) && start_time.is_none_or(|start| process_start_time(pid) == Some(start))
+}
+
+/// Check whether a process has exited but not yet been reaped (Linux only)
+fn is_zombie(pid: libc::pid_t) -> bool {
+    // The state field follows the parenthesised command name in /proc/<pid>/stat
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| {
+            let (_, rest) = stat.rsplit_once(')')?;
+            rest.split_whitespace().next().map(|state| state == "Z")
+        })
+        .unwrap_or(false)
+}
+
+/// Send a signal to a process
+pub fn send_signal(pid: u32, signal: libc::c_int) -> Result<()> {
+    let pid = libc::pid_t::try_from(pid)?;
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        let err = std::io::Error::last_os_error();
+        anyhow::bail!("Failed to signal process {}: {}", pid, err);
+    }
+    Ok(())
+}
+
+/// Wait until a process exits, returning false if it is still alive after `timeout`
+pub fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while is_process_alive(pid) {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    true
+}
+
+/// Pid of the foreground child that received signals are forwarded to (0 = none)
+static FORWARD_PID: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward_signal(signal: libc::c_int) {
+    let pid = FORWARD_PID.load(Ordering::SeqCst);
+    if pid > 0 {
+        // kill() is async-signal-safe
+        unsafe { libc::kill(pid, signal) };
+    }
+}
+
+/// Forward SIGINT, SIGTERM and SIGHUP received by vex to a child process.
+///
+/// vex keeps running so it can clean up and report the child's exit status.
+pub fn forward_signals_to(pid: u32) {
+    FORWARD_PID.store(libc::pid_t::try_from(pid).unwrap_or(0), Ordering::SeqCst);
+    let handler = forward_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        unsafe { libc::signal(signal, handler) };
+    }
+}
+
+/// Stop forwarding signals and restore their default handling
+pub fn stop_forwarding_signals() {
+    FORWARD_PID.store(0, Ordering::SeqCst);
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        unsafe { libc::signal(signal, libc::SIG_DFL) };
+    }
+}
+
+/// Convert an exit status to a shell-style code: the exit code, or 128 + signal number
+pub fn exit_code(status: ExitStatus) -> u8 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code as u8,
+        (None, Some(signal)) => (128 + signal) as u8,
+        (None, None) => 1,
+    }
+}
+
+/// Wait for a child, returning `None` if it is still running after `timeout`
+pub fn wait_timeout(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
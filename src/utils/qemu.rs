@@ -1,6 +1,10 @@
 use regex::Regex;
 use std::process::Command;
 
+use crate::config::Protocol;
+use crate::utils::qemu_args::{OptionValue, QemuArg, QemuArgs, Suboptions};
+use crate::utils::qemu_options::lookup;
+
 /// Get QEMU version string (e.g., "8.2.0")
 pub fn get_qemu_version(binary: &str) -> Option<String> {
     let output = Command::new(binary).arg("--version").output().ok()?;
@@ -17,3 +21,144 @@ pub fn get_qemu_version(binary: &str) -> Option<String> {
 
     re.captures(&output_str).map(|caps| caps[1].to_string())
 }
+
+/// Canonical name of an option QEMU only honours once, so `-M` matches `-machine`
+fn single_valued_key(name: &str) -> Option<&'static str> {
+    lookup(name)
+        .filter(|spec| spec.takes_arg && !spec.repeatable)
+        .map(|spec| spec.name)
+}
+
+/// Merge runtime arguments into saved ones.
+///
+/// Single-valued options such as `-m` or `-kernel` replace the saved value
+/// in place; everything else (e.g. `-device`, `-drive`) is appended.
+pub fn merge_args(base: &[String], overrides: &[String]) -> Vec<String> {
+    let mut merged = QemuArgs::parse(base);
+    merge_items(
+        &mut merged.items,
+        QemuArgs::parse(overrides).items,
+        QemuArg::name,
+    );
+    merged.to_argv()
+}
+
+/// Merge parsed options the way [`merge_args`] merges argv, naming each item with `name`
+pub fn merge_items<T: Clone>(
+    merged: &mut Vec<T>,
+    overrides: Vec<T>,
+    name: impl Fn(&T) -> Option<&str>,
+) {
+    for item in overrides {
+        let Some(key) = name(&item).and_then(single_valued_key) else {
+            merged.push(item);
+            continue;
+        };
+        let mut replaced = false;
+        merged.retain_mut(|existing| {
+            if name(existing).and_then(single_valued_key) != Some(key) {
+                return true;
+            }
+            if replaced {
+                // Drop duplicates of an option QEMU only takes once
+                return false;
+            }
+            *existing = item.clone();
+            replaced = true;
+            true
+        });
+        if !replaced {
+            merged.push(item);
+        }
+    }
+}
+
+/// Options that can define a user-mode (slirp) network
+const NETWORK_OPTIONS: &[&str] = &["-netdev", "-nic", "-net"];
+
+/// Suboptions of a user-mode network option such as `-netdev user,id=n0`
+fn user_network(item: &QemuArg) -> Option<&Suboptions> {
+    item.name()
+        .filter(|name| NETWORK_OPTIONS.contains(name))
+        .and(item.value())
+        .and_then(OptionValue::suboptions)
+        .filter(|subopts| subopts.implied() == Some("user"))
+}
+
+/// Add `hostfwd=` rules to the first user-mode network in `args`.
+///
+/// Without a user-mode network, `-nic user` is added to carry the rules.
+pub fn add_host_forwards(args: &mut Vec<String>, hostfwds: &[String]) {
+    if hostfwds.is_empty() {
+        return;
+    }
+    let mut parsed = QemuArgs::parse(args);
+    let index = match parsed
+        .items
+        .iter()
+        .position(|item| user_network(item).is_some())
+    {
+        Some(index) => index,
+        None => {
+            parsed.push("-nic", "user");
+            parsed.items.len() - 1
+        }
+    };
+    if let Some(net) = parsed.items[index]
+        .value_mut()
+        .and_then(OptionValue::suboptions_mut)
+    {
+        for rule in hostfwds {
+            net.push("hostfwd", rule);
+        }
+    }
+    *args = parsed.to_argv();
+}
+
+/// `hostfwd=` rule: `[tcp|udp]:[HOSTADDR]:HOSTPORT-[GUESTADDR]:GUESTPORT`
+const HOSTFWD_PATTERN: &str = r"^(tcp|udp)?:([^:]*):(\d+)-([^:]*):(\d+)$";
+
+/// Find the host address and port that a `hostfwd=` rule maps to a guest TCP port
+pub fn find_host_forward(args: &[String], guest_port: u16) -> Option<(String, u16)> {
+    let re = Regex::new(HOSTFWD_PATTERN).unwrap();
+    let parsed = QemuArgs::parse(args);
+    parsed
+        .items
+        .iter()
+        .filter_map(user_network)
+        .flat_map(|net| net.get_all("hostfwd"))
+        .filter_map(|rule| re.captures(rule))
+        .find(|caps| {
+            caps.get(1).is_none_or(|proto| proto.as_str() == "tcp")
+                && caps[5].parse() == Ok(guest_port)
+        })
+        .and_then(|caps| {
+            let addr = match &caps[2] {
+                "" => "127.0.0.1".to_string(),
+                addr => addr.to_string(),
+            };
+            Some((addr, caps[3].parse().ok()?))
+        })
+}
+
+/// Protocol, host address and host port of every `hostfwd=` rule in `args`.
+///
+/// An empty address means QEMU binds the port on all interfaces.
+pub fn host_forward_ports(args: &[String]) -> Vec<(Protocol, String, u16)> {
+    let re = Regex::new(HOSTFWD_PATTERN).unwrap();
+    let parsed = QemuArgs::parse(args);
+    parsed
+        .items
+        .iter()
+        .filter_map(user_network)
+        .flat_map(|net| net.get_all("hostfwd"))
+        .filter_map(|rule| {
+            let caps = re.captures(rule)?;
+            let proto = match caps.get(1).map(|proto| proto.as_str()) {
+                Some("udp") => Protocol::Udp,
+                _ => Protocol::Tcp,
+            };
+            Some((proto, caps[2].to_string(), caps[3].parse().ok()?))
+        })
+        .collect()
+}
@@ -0,0 +1,373 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::utils::qemu_options::{canonical_name, tokenize};
+
+/// Options whose value is a free-form string rather than `key=value,...` suboptions
+const PLAIN_VALUE_OPTIONS: &[&str] = &[
+    "-append",
+    "-kernel",
+    "-initrd",
+    "-dtb",
+    "-bios",
+    "-pidfile",
+    "-loadvm",
+    "-hda",
+    "-hdb",
+    "-hdc",
+    "-hdd",
+    "-cdrom",
+    "-fda",
+    "-fdb",
+    "-mtdblock",
+    "-sd",
+    "-pflash",
+    "-D",
+    "-L",
+    "-uuid",
+    "-k",
+];
+
+/// Plain-valued options whose value is a file or directory path
+const PATH_OPTIONS: &[&str] = &[
+    "-kernel",
+    "-initrd",
+    "-dtb",
+    "-bios",
+    "-pidfile",
+    "-hda",
+    "-hdb",
+    "-hdc",
+    "-hdd",
+    "-cdrom",
+    "-fda",
+    "-fdb",
+    "-mtdblock",
+    "-sd",
+    "-pflash",
+    "-D",
+    "-L",
+];
+
+/// Suboption keys whose value is a file or directory path, e.g. `-drive file=...`
+const PATH_SUBOPTIONS: &[&str] = &[
+    "file",
+    "filename",
+    "file.filename",
+    "path",
+    "script",
+    "downscript",
+    "romfile",
+    "mem-path",
+];
+
+/// QEMU argv parsed into options and their values.
+///
+/// Every token is kept as written, so `to_argv` returns the original argv.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QemuArgs {
+    pub items: Vec<QemuArg>,
+}
+
+/// One element of a QEMU command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QemuArg {
+    /// An option with an optional value, e.g. `-m 2G` or `-nographic`
+    Option {
+        /// The option as written, e.g. `-drive` or `--drive`
+        token: String,
+        value: Option<OptionValue>,
+    },
+    /// A token that isn't an option, such as a bare disk image
+    Positional(String),
+}
+
+/// Value of an option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    /// A free-form string, e.g. the kernel command line of `-append`
+    Plain(String),
+    /// Comma-separated suboptions, e.g. `file=disk.qcow2,if=virtio`
+    Suboptions(Suboptions),
+}
+
+/// Comma-separated `key=value` list in QEMU syntax, where `,,` is a literal comma
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suboptions {
+    pub entries: Vec<Suboption>,
+}
+
+/// One entry of a suboption list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suboption {
+    /// Key of a `key=value` entry; `None` for bare entries such as `virtio-net-pci`
+    pub key: Option<String>,
+    pub value: String,
+}
+
+impl QemuArgs {
+    /// Group argv into options and values using the QEMU option table
+    pub fn parse(args: &[String]) -> Self {
+        let items = tokenize(args)
+            .into_iter()
+            .map(|token| match (token.option, token.value) {
+                (Some(option), value) => QemuArg::Option {
+                    token: option.to_string(),
+                    value: value.map(|value| OptionValue::parse(canonical_name(option), value)),
+                },
+                (None, value) => QemuArg::Positional(value.unwrap_or_default().to_string()),
+            })
+            .collect();
+        QemuArgs { items }
+    }
+
+    /// Serialise back to argv
+    pub fn to_argv(&self) -> Vec<String> {
+        self.items.iter().flat_map(QemuArg::to_argv).collect()
+    }
+
+    /// All occurrences of an option, matched by canonical name
+    pub fn options<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a QemuArg> + 'a {
+        self.items
+            .iter()
+            .filter(move |item| item.name() == Some(name))
+    }
+
+    /// Value of the last occurrence of an option, which is the one QEMU honours
+    pub fn value(&self, name: &str) -> Option<&OptionValue> {
+        self.items
+            .iter()
+            .rev()
+            .filter(|item| item.name() == Some(name))
+            .find_map(QemuArg::value)
+    }
+
+    /// Append an option with a value
+    pub fn push(&mut self, option: &str, value: &str) {
+        self.items.push(QemuArg::Option {
+            token: option.to_string(),
+            value: Some(OptionValue::parse(canonical_name(option), value)),
+        });
+    }
+}
+
+impl QemuArg {
+    /// Canonical option name, or `None` for positional arguments
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            QemuArg::Option { token, .. } => Some(canonical_name(token)),
+            QemuArg::Positional(_) => None,
+        }
+    }
+
+    pub fn value(&self) -> Option<&OptionValue> {
+        match self {
+            QemuArg::Option { value, .. } => value.as_ref(),
+            QemuArg::Positional(_) => None,
+        }
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut OptionValue> {
+        match self {
+            QemuArg::Option { value, .. } => value.as_mut(),
+            QemuArg::Positional(_) => None,
+        }
+    }
+
+    /// Make relative file paths absolute against `dir`, so the argument means the same from anywhere
+    pub fn rebase_paths(&mut self, dir: &Path) {
+        match self {
+            QemuArg::Positional(arg) => rebase_path(arg, dir),
+            QemuArg::Option {
+                token,
+                value: Some(value),
+            } => {
+                let name = canonical_name(token);
+                match value {
+                    OptionValue::Plain(path) if PATH_OPTIONS.contains(&name) => {
+                        rebase_path(path, dir)
+                    }
+                    OptionValue::Suboptions(subopts) => {
+                        for entry in &mut subopts.entries {
+                            let Some(key) = entry.key.as_deref() else {
+                                continue;
+                            };
+                            // A blockdev's `file` names another node rather than a file
+                            if PATH_SUBOPTIONS.contains(&key)
+                                && !(name == "-blockdev" && key == "file")
+                            {
+                                rebase_path(&mut entry.value, dir);
+                            }
+                        }
+                    }
+                    OptionValue::Plain(_) => {}
+                }
+            }
+            QemuArg::Option { value: None, .. } => {}
+        }
+    }
+
+    /// Serialise back to one or two argv tokens
+    pub fn to_argv(&self) -> Vec<String> {
+        match self {
+            QemuArg::Option { token, value } => std::iter::once(token.clone())
+                .chain(value.as_ref().map(ToString::to_string))
+                .collect(),
+            QemuArg::Positional(arg) => vec![arg.clone()],
+        }
+    }
+}
+
+impl OptionValue {
+    /// Parse the value of `option` (canonical name), splitting suboptions where QEMU does
+    pub fn parse(option: &str, value: &str) -> Self {
+        if PLAIN_VALUE_OPTIONS.contains(&option) {
+            OptionValue::Plain(value.to_string())
+        } else {
+            OptionValue::Suboptions(Suboptions::parse(value))
+        }
+    }
+
+    pub fn suboptions(&self) -> Option<&Suboptions> {
+        match self {
+            OptionValue::Suboptions(subopts) => Some(subopts),
+            OptionValue::Plain(_) => None,
+        }
+    }
+
+    pub fn suboptions_mut(&mut self) -> Option<&mut Suboptions> {
+        match self {
+            OptionValue::Suboptions(subopts) => Some(subopts),
+            OptionValue::Plain(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionValue::Plain(value) => f.write_str(value),
+            OptionValue::Suboptions(subopts) => subopts.fmt(f),
+        }
+    }
+}
+
+impl Suboptions {
+    /// Split on single commas, unescaping `,,`; each entry splits at its first `=`
+    pub fn parse(value: &str) -> Self {
+        let mut entries = Vec::new();
+        let mut current = String::new();
+        let mut chars = value.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != ',' {
+                current.push(c);
+            } else if chars.next_if_eq(&',').is_some() {
+                current.push(',');
+            } else {
+                entries.push(Suboption::parse(&std::mem::take(&mut current)));
+            }
+        }
+        entries.push(Suboption::parse(&current));
+        Suboptions { entries }
+    }
+
+    /// Value of the last entry with `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.key.as_deref() == Some(key))
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// All values of a repeatable key such as `hostfwd`
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.key.as_deref() == Some(key))
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// The leading bare entry, e.g. the driver of `-device` or the backend of `-netdev`
+    pub fn implied(&self) -> Option<&str> {
+        self.entries
+            .first()
+            .filter(|entry| entry.key.is_none())
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// Replace the value of `key` in place, or append it
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.key.as_deref() == Some(key))
+        {
+            Some(entry) => entry.value = value.to_string(),
+            None => self.push(key, value),
+        }
+    }
+
+    /// Append a `key=value` entry, keeping existing ones
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.entries.push(Suboption {
+            key: Some(key.to_string()),
+            value: value.to_string(),
+        });
+    }
+}
+
+impl fmt::Display for Suboptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            if let Some(key) = &entry.key {
+                write!(f, "{}=", key.replace(',', ",,"))?;
+            }
+            f.write_str(&entry.value.replace(',', ",,"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Suboption {
+    fn parse(entry: &str) -> Self {
+        match entry.split_once('=') {
+            Some((key, value)) => Suboption {
+                key: Some(key.to_string()),
+                value: value.to_string(),
+            },
+            None => Suboption {
+                key: None,
+                value: entry.to_string(),
+            },
+        }
+    }
+}
+
+/// Join a relative path onto `dir`, leaving variables and protocol paths such as `nbd:...` alone
+fn rebase_path(value: &mut String, dir: &Path) {
+    let path = Path::new(value.as_str());
+    if value.is_empty() || path.is_absolute() || value.contains("${") || value.contains(':') {
+        return;
+    }
+    *value = normalize_path(&dir.join(path)).display().to_string();
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,252 @@
+/// What Vex knows about a QEMU command-line option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionSpec {
+    /// Canonical spelling, e.g. `-machine`
+    pub name: &'static str,
+    /// Other spellings of the same option, e.g. `-M`
+    pub aliases: &'static [&'static str],
+    /// Whether the following argv element is the option's value
+    pub takes_arg: bool,
+    /// Whether QEMU accepts the option more than once
+    pub repeatable: bool,
+    /// Whether QEMU has deprecated the option
+    pub deprecated: bool,
+}
+
+impl OptionSpec {
+    /// Option with a value that QEMU honours once
+    const fn single(name: &'static str) -> Self {
+        OptionSpec {
+            name,
+            aliases: &[],
+            takes_arg: true,
+            repeatable: false,
+            deprecated: false,
+        }
+    }
+
+    /// Option with a value that may be given several times
+    const fn multi(name: &'static str) -> Self {
+        OptionSpec {
+            repeatable: true,
+            ..Self::single(name)
+        }
+    }
+
+    /// Option without a value
+    const fn flag(name: &'static str) -> Self {
+        OptionSpec {
+            takes_arg: false,
+            repeatable: true,
+            ..Self::single(name)
+        }
+    }
+
+    const fn alias(self, aliases: &'static [&'static str]) -> Self {
+        OptionSpec { aliases, ..self }
+    }
+
+    const fn deprecated(self) -> Self {
+        OptionSpec {
+            deprecated: true,
+            ..self
+        }
+    }
+}
+
+/// QEMU options Vex understands; unknown options fall back to guessing from argv
+const OPTIONS: &[OptionSpec] = &[
+    // Machine and CPU
+    OptionSpec::single("-machine").alias(&["-M"]),
+    OptionSpec::single("-cpu"),
+    OptionSpec::multi("-accel"),
+    OptionSpec::single("-smp"),
+    OptionSpec::single("-m"),
+    OptionSpec::single("-mem-path"),
+    OptionSpec::flag("-mem-prealloc"),
+    OptionSpec::multi("-numa"),
+    OptionSpec::single("-overcommit"),
+    OptionSpec::flag("-enable-kvm"),
+    OptionSpec::flag("-no-kvm").deprecated(),
+    OptionSpec::flag("-no-acpi").deprecated(),
+    OptionSpec::flag("-no-hpet").deprecated(),
+    OptionSpec::flag("-singlestep").deprecated(),
+    OptionSpec::single("-icount"),
+    OptionSpec::single("-rtc"),
+    OptionSpec::single("-uuid"),
+    OptionSpec::single("-name"),
+    OptionSpec::single("-k"),
+    // Boot
+    OptionSpec::single("-kernel"),
+    OptionSpec::single("-initrd"),
+    OptionSpec::single("-append"),
+    OptionSpec::single("-dtb"),
+    OptionSpec::single("-bios"),
+    OptionSpec::single("-boot"),
+    OptionSpec::multi("-L"),
+    OptionSpec::multi("-fw_cfg"),
+    OptionSpec::multi("-smbios"),
+    // Block devices
+    OptionSpec::multi("-drive"),
+    OptionSpec::multi("-blockdev"),
+    OptionSpec::single("-hda"),
+    OptionSpec::single("-hdb"),
+    OptionSpec::single("-hdc"),
+    OptionSpec::single("-hdd"),
+    OptionSpec::single("-cdrom"),
+    OptionSpec::single("-fda"),
+    OptionSpec::single("-fdb"),
+    OptionSpec::multi("-pflash"),
+    OptionSpec::multi("-sd"),
+    OptionSpec::multi("-mtdblock"),
+    OptionSpec::flag("-snapshot"),
+    OptionSpec::multi("-fsdev"),
+    OptionSpec::multi("-virtfs"),
+    // Devices and backends
+    OptionSpec::multi("-device"),
+    OptionSpec::multi("-global"),
+    OptionSpec::multi("-object"),
+    OptionSpec::multi("-chardev"),
+    OptionSpec::multi("-audiodev"),
+    OptionSpec::multi("-tpmdev"),
+    OptionSpec::multi("-usbdevice").deprecated(),
+    OptionSpec::single("-soundhw").deprecated(),
+    OptionSpec::multi("-add-fd"),
+    // Network
+    OptionSpec::multi("-netdev"),
+    OptionSpec::multi("-nic"),
+    OptionSpec::multi("-net"),
+    // Display and consoles
+    OptionSpec::single("-display"),
+    OptionSpec::flag("-nographic"),
+    OptionSpec::single("-vga"),
+    OptionSpec::single("-vnc"),
+    OptionSpec::single("-spice"),
+    OptionSpec::flag("-full-screen"),
+    OptionSpec::multi("-serial"),
+    OptionSpec::multi("-parallel"),
+    OptionSpec::multi("-monitor"),
+    OptionSpec::multi("-mon"),
+    OptionSpec::multi("-qmp"),
+    OptionSpec::multi("-qmp-pretty"),
+    // Debugging
+    OptionSpec::multi("-gdb"),
+    OptionSpec::flag("-s"),
+    OptionSpec::flag("-S"),
+    OptionSpec::single("-d"),
+    OptionSpec::single("-D"),
+    OptionSpec::multi("-trace"),
+    OptionSpec::multi("-plugin"),
+    OptionSpec::single("-semihosting-config"),
+    // Lifecycle
+    OptionSpec::flag("-no-reboot"),
+    OptionSpec::flag("-no-shutdown"),
+    OptionSpec::multi("-action"),
+    OptionSpec::single("-watchdog-action"),
+    OptionSpec::flag("-daemonize"),
+    OptionSpec::single("-pidfile"),
+    OptionSpec::single("-loadvm"),
+    OptionSpec::single("-incoming"),
+    OptionSpec::flag("-only-migratable"),
+    // Configuration and sandboxing
+    OptionSpec::flag("-nodefaults"),
+    OptionSpec::flag("-no-user-config"),
+    OptionSpec::multi("-readconfig"),
+    OptionSpec::multi("-set"),
+    OptionSpec::single("-sandbox"),
+    OptionSpec::single("-compat"),
+    OptionSpec::single("-msg"),
+    OptionSpec::single("-runas").deprecated(),
+    OptionSpec::single("-chroot").deprecated(),
+    OptionSpec::single("-run-with"),
+];
+
+/// Canonical spelling of an option, so `--machine` matches `-machine`
+pub fn option_name(token: &str) -> &str {
+    token
+        .strip_prefix('-')
+        .filter(|o| o.starts_with('-'))
+        .unwrap_or(token)
+}
+
+/// Look up an option by any of its spellings, e.g. `-M` or `--machine`
+pub fn lookup(token: &str) -> Option<&'static OptionSpec> {
+    let name = option_name(token);
+    OPTIONS
+        .iter()
+        .find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+/// Canonical name of an option, resolving aliases such as `-M` for known options
+pub fn canonical_name(token: &str) -> &str {
+    lookup(token).map_or(option_name(token), |spec| spec.name)
+}
+
+/// An `(option, value)` pair from QEMU argv.
+///
+/// Both parts are kept as written. Positional arguments such as a bare disk
+/// image have no option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub option: Option<&'a str>,
+    pub value: Option<&'a str>,
+}
+
+impl<'a> Token<'a> {
+    /// Canonical name of the option, with aliases resolved for known options
+    pub fn name(&self) -> Option<&'a str> {
+        self.option.map(canonical_name)
+    }
+
+    pub fn spec(&self) -> Option<&'static OptionSpec> {
+        self.option.and_then(lookup)
+    }
+
+    /// Whether this is one of the given options, matched by canonical name
+    pub fn is(&self, names: &[&str]) -> bool {
+        self.name().is_some_and(|name| names.contains(&name))
+    }
+
+    /// The argv elements this token was read from
+    pub fn to_argv(&self) -> Vec<String> {
+        self.option
+            .into_iter()
+            .chain(self.value)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Split QEMU argv into `(option, value)` pairs.
+///
+/// Known options take the next element as their value exactly when the table
+/// says so, even if it starts with `-`. Unknown options take the next element
+/// unless it looks like an option itself.
+pub fn tokenize(args: &[String]) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with('-') {
+            tokens.push(Token {
+                option: None,
+                value: Some(arg),
+            });
+            continue;
+        }
+        let value = match lookup(arg) {
+            Some(spec) if spec.takes_arg => iter.next(),
+            Some(_) => None,
+            None => iter.next_if(|next| !next.starts_with('-')),
+        };
+        tokens.push(Token {
+            option: Some(arg),
+            value: value.map(String::as_str),
+        });
+    }
+    tokens
+}
+
+/// Rebuild argv from tokens
+pub fn untokenize(tokens: &[Token]) -> Vec<String> {
+    tokens.iter().flat_map(Token::to_argv).collect()
+}
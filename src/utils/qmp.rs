@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Minimal QMP (QEMU Machine Protocol) client over a unix socket
+pub struct QmpClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    /// Greeting sent by QEMU on connect, including its version
+    greeting: Value,
+    /// Asynchronous events received while waiting for command replies
+    events: VecDeque<Value>,
+    next_id: u64,
+}
+
+impl QmpClient {
+    /// Connect to a QMP socket and complete the capabilities handshake
+    pub fn connect(path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(path)
+            .with_context(|| format!("Failed to connect to QMP socket {}", path.display()))?;
+        let writer = stream.try_clone().context("Failed to clone QMP socket")?;
+        let mut client = QmpClient {
+            reader: BufReader::new(stream),
+            writer,
+            greeting: Value::Null,
+            events: VecDeque::new(),
+            next_id: 0,
+        };
+
+        client.greeting = client.read_message()?;
+        if client.greeting.get("QMP").is_none() {
+            anyhow::bail!("Unexpected QMP greeting: {}", client.greeting);
+        }
+        client.execute("qmp_capabilities", None)?;
+
+        Ok(client)
+    }
+
+    /// Connect to a QMP socket, retrying until QEMU creates it or `timeout` expires
+    pub fn connect_timeout(path: &Path, timeout: Duration) -> Result<Self> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match Self::connect(path) {
+                Ok(client) => return Ok(client),
+                Err(err) if Instant::now() >= deadline => return Err(err),
+                Err(_) => thread::sleep(Duration::from_millis(100)),
+            }
+        }
+    }
+
+    /// Greeting sent by QEMU on connect
+    pub fn greeting(&self) -> &Value {
+        &self.greeting
+    }
+
+    /// Execute a QMP command and return its `return` value
+    pub fn execute(&mut self, command: &str, arguments: Option<Value>) -> Result<Value> {
+        self.next_id += 1;
+        let id = format!("vex-{}", self.next_id);
+        let mut request = json!({ "execute": command, "id": id });
+        if let Some(arguments) = arguments {
+            request["arguments"] = arguments;
+        }
+
+        let mut line = serde_json::to_string(&request).context("Failed to encode QMP command")?;
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .context("Failed to send QMP command")?;
+
+        loop {
+            let message = self.read_message()?;
+            if message.get("event").is_some() {
+                self.events.push_back(message);
+                continue;
+            }
+            if message.get("id").and_then(Value::as_str) != Some(id.as_str()) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                anyhow::bail!(
+                    "QMP command '{}' failed: {} ({})",
+                    command,
+                    error["desc"].as_str().unwrap_or("unknown error"),
+                    error["class"].as_str().unwrap_or("GenericError")
+                );
+            }
+            return Ok(message.get("return").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    /// Wait for the next asynchronous event
+    pub fn next_event(&mut self) -> Result<Value> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+        loop {
+            let message = self.read_message()?;
+            if message.get("event").is_some() {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Take all events received so far
+    pub fn take_events(&mut self) -> Vec<Value> {
+        self.events.drain(..).collect()
+    }
+
+    /// Read one JSON message; QMP sends one message per line
+    fn read_message(&mut self) -> Result<Value> {
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .context("Failed to read from QMP socket")?;
+        if read == 0 {
+            anyhow::bail!("QMP connection closed by QEMU");
+        }
+        serde_json::from_str(&line).context("Failed to parse QMP message")
+    }
+}
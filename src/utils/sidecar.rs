@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::utils::process::{exit_code, is_process_alive, send_signal, wait_for_exit};
+
+/// Seconds to wait for a sidecar's socket unless the configuration says otherwise
+const DEFAULT_SOCKET_TIMEOUT: u64 = 10;
+
+/// Sidecar resolved for one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidecarRun {
+    pub name: String,
+    /// Program and arguments after substitution
+    pub command: Vec<String>,
+    /// Socket the sidecar creates for QEMU
+    pub socket: PathBuf,
+    /// Seconds to wait for the socket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// Start sidecars in order, waiting for each one's socket, and return their pids.
+///
+/// Their output goes to `log`. If one fails to come up, those already started are stopped.
+pub fn start_sidecars(
+    sidecars: &[SidecarRun],
+    log: &File,
+    log_path: &Path,
+    cwd: Option<&Path>,
+) -> Result<BTreeMap<String, u32>> {
+    let mut pids = BTreeMap::new();
+    for sidecar in sidecars {
+        match start_sidecar(sidecar, log, log_path, cwd) {
+            Ok(pid) => {
+                pids.insert(sidecar.name.clone(), pid);
+            }
+            Err(err) => {
+                stop_sidecars(&pids);
+                return Err(err);
+            }
+        }
+    }
+    Ok(pids)
+}
+
+/// Start one sidecar and wait until its socket exists
+fn start_sidecar(
+    sidecar: &SidecarRun,
+    log: &File,
+    log_path: &Path,
+    cwd: Option<&Path>,
+) -> Result<u32> {
+    let _ = fs::remove_file(&sidecar.socket);
+    println!("Starting sidecar '{}'", sidecar.name);
+
+    let (program, args) = sidecar
+        .command
+        .split_first()
+        .with_context(|| format!("Sidecar '{}' has an empty command", sidecar.name))?;
+    let mut command = Command::new(program);
+    if let Some(dir) = cwd {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(
+            log.try_clone()
+                .context("Failed to open run log for sidecar")?,
+        )
+        .stderr(
+            log.try_clone()
+                .context("Failed to open run log for sidecar")?,
+        )
+        // Own process group, so Ctrl-C reaches QEMU first and sidecars are stopped after it
+        .process_group(0)
+        .spawn()
+        .with_context(|| format!("Failed to start sidecar '{}': {}", sidecar.name, program))?;
+
+    let timeout = sidecar.timeout.unwrap_or(DEFAULT_SOCKET_TIMEOUT);
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+    while !sidecar.socket.exists() {
+        if let Some(status) = child
+            .try_wait()
+            .context("Failed to check sidecar process")?
+        {
+            anyhow::bail!(
+                "Sidecar '{}' exited with exit code {} before creating {}. See log: {}",
+                sidecar.name,
+                exit_code(status),
+                sidecar.socket.display(),
+                log_path.display()
+            );
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!(
+                "Sidecar '{}' did not create {} within {}s. See log: {}",
+                sidecar.name,
+                sidecar.socket.display(),
+                timeout,
+                log_path.display()
+            );
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    Ok(child.id())
+}
+
+/// Stop sidecars with SIGTERM, killing those that don't exit within a few seconds
+pub fn stop_sidecars(pids: &BTreeMap<String, u32>) {
+    for &pid in pids.values() {
+        if !is_process_alive(pid) {
+            continue;
+        }
+        let _ = send_signal(pid, libc::SIGTERM);
+        if !wait_for_exit(pid, Duration::from_secs(5)) {
+            let _ = send_signal(pid, libc::SIGKILL);
+            wait_for_exit(pid, Duration::from_secs(2));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::utils::qemu_args::{OptionValue, QemuArgs, Suboptions};
+
+/// Internal snapshot stored in a qcow2 image
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    /// Snapshot tag
+    pub name: String,
+    /// Size of the saved VM state in bytes (0 for disk-only snapshots)
+    pub vm_state_size: u64,
+    /// Creation time in seconds since the Unix epoch
+    pub date_sec: u64,
+}
+
+impl SnapshotInfo {
+    /// Parse a snapshot entry from `qemu-img info` or QMP `query-block` JSON
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(SnapshotInfo {
+            name: value.get("name")?.as_str()?.to_string(),
+            vm_state_size: value
+                .get("vm-state-size")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+            date_sec: value.get("date-sec").and_then(Value::as_u64).unwrap_or(0),
+        })
+    }
+}
+
+/// Find the qcow2 disk images referenced by QEMU arguments
+///
+/// Looks at `-drive file=...` (qcow2 format or `.qcow2` extension), qcow2
+/// `-blockdev` nodes and the `-hda`..`-hdd` shorthands.
+pub fn qcow2_drives(args: &[String]) -> Vec<PathBuf> {
+    let args = QemuArgs::parse(args);
+    let mut drives = Vec::new();
+    for item in &args.items {
+        let (Some(name), Some(value)) = (item.name(), item.value()) else {
+            continue;
+        };
+        match (name, value) {
+            ("-drive", OptionValue::Suboptions(opts)) => {
+                let format = opts.get("format");
+                if let Some(file) = opts.get("file")
+                    && (format == Some("qcow2") || format.is_none() && is_qcow2_path(file))
+                {
+                    drives.push(PathBuf::from(file));
+                }
+            }
+            ("-blockdev", OptionValue::Suboptions(opts)) if opts.get("driver") == Some("qcow2") => {
+                if let Some(file) = blockdev_filename(&args, opts) {
+                    drives.push(PathBuf::from(file));
+                }
+            }
+            ("-hda" | "-hdb" | "-hdc" | "-hdd", OptionValue::Plain(file))
+                if is_qcow2_path(file) =>
+            {
+                drives.push(PathBuf::from(file));
+            }
+            _ => {}
+        }
+    }
+    drives
+}
+
+/// Image of a qcow2 `-blockdev`, given inline as `file.filename` or as the node named by `file`
+fn blockdev_filename<'a>(args: &'a QemuArgs, opts: &'a Suboptions) -> Option<&'a str> {
+    if let Some(file) = opts.get("file.filename") {
+        return Some(file);
+    }
+    let node = opts.get("file")?;
+    args.options("-blockdev")
+        .filter_map(|item| item.value()?.suboptions())
+        .find(|protocol| protocol.get("node-name") == Some(node))
+        .and_then(|protocol| protocol.get("filename"))
+}
+
+fn is_qcow2_path(path: &str) -> bool {
+    path.ends_with(".qcow2") || path.ends_with(".qcow")
+}
+
+/// Locate `qemu-img`, preferring the one installed next to the QEMU binary
+pub fn qemu_img_bin(qemu_bin: &str) -> PathBuf {
+    let sibling = Path::new(qemu_bin)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join("qemu-img"));
+    match sibling {
+        Some(path) if path.exists() => path,
+        _ => PathBuf::from("qemu-img"),
+    }
+}
+
+/// List the internal snapshots of an image that is not in use
+pub fn list_image_snapshots(qemu_img: &Path, image: &Path) -> Result<Vec<SnapshotInfo>> {
+    let output = Command::new(qemu_img)
+        .args(["info", "--output=json"])
+        .arg(image)
+        .output()
+        .with_context(|| format!("Failed to run {}", qemu_img.display()))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "qemu-img info failed for {}: {}",
+            image.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let info: Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse qemu-img output")?;
+    Ok(info
+        .get("snapshots")
+        .and_then(Value::as_array)
+        .map(|list| list.iter().filter_map(SnapshotInfo::from_json).collect())
+        .unwrap_or_default())
+}
+
+/// Run `qemu-img snapshot` with an action flag (`-c`, `-a` or `-d`) on an image
+pub fn image_snapshot(qemu_img: &Path, action: &str, tag: &str, image: &Path) -> Result<()> {
+    let output = Command::new(qemu_img)
+        .args(["snapshot", action, tag])
+        .arg(image)
+        .output()
+        .with_context(|| format!("Failed to run {}", qemu_img.display()))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "qemu-img snapshot {} failed for {}: {}",
+            action,
+            image.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::num::{IntErrorKind, ParseIntError};
+use std::time::Duration;
+
+/// Format a number of seconds as a compact duration (e.g. "45s", "3m12s", "2h05m")
+pub fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h{:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m{:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Parse a duration such as `90`, `90s`, `500ms`, `5m` or `1h` (plain numbers are seconds)
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let too_large = || format!("duration '{}' is too large", s);
+    let value: u64 = number
+        .parse()
+        .map_err(|err: ParseIntError| match err.kind() {
+            IntErrorKind::PosOverflow => too_large(),
+            _ => format!("invalid duration '{}': expected e.g. 90s, 5m or 1h", s),
+        })?;
+    let secs = |factor: u64| {
+        value
+            .checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(too_large)
+    };
+    match unit {
+        "" | "s" => secs(1),
+        "ms" => Ok(Duration::from_millis(value)),
+        "m" => secs(60),
+        "h" => secs(3600),
+        _ => Err(format!(
+            "invalid duration unit '{}' in '{}': use ms, s, m or h",
+            unit, s
+        )),
+    }
+}
+
+/// Format seconds since the Unix epoch as a UTC date and time (`2024-01-31 13:45:00`)
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day, h, m, s) = civil_from_unix(secs);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, h, m, s
+    )
+}
+
+/// Compact UTC timestamp usable in file names (`20240131-134500`)
+pub fn file_timestamp(secs: u64) -> String {
+    let (year, month, day, h, m, s) = civil_from_unix(secs);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, h, m, s)
+}
+
+/// Split Unix seconds into UTC (year, month, day, hour, minute, second)
+fn civil_from_unix(secs: u64) -> (i64, u64, u64, u64, u64, u64) {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // Howard Hinnant's days-to-civil algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, rem / 3600, (rem % 3600) / 60, rem % 60)
+}